@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE assemblies DROP COLUMN minecraft_version;
+DROP TABLE IF EXISTS plugin_version_game_versions;
+ALTER TABLE plugin_versions DROP COLUMN api_version;
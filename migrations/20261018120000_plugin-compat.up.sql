@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TABLE plugin_versions ADD COLUMN api_version TEXT;
+
+CREATE TABLE plugin_version_game_versions (
+    version_id TEXT NOT NULL REFERENCES plugin_versions(id) ON DELETE CASCADE,
+    game_version TEXT NOT NULL,
+    PRIMARY KEY (version_id, game_version)
+);
+
+ALTER TABLE assemblies ADD COLUMN minecraft_version TEXT;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE assemblies DROP COLUMN java_version;
+ALTER TABLE plugin_versions DROP COLUMN min_java_version;
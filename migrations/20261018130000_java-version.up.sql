@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE plugin_versions ADD COLUMN min_java_version INTEGER;
+
+ALTER TABLE assemblies ADD COLUMN java_version INTEGER;
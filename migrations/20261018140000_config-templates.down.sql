@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE assemblies DROP COLUMN variables;
+DROP TABLE IF EXISTS assembly_configs;
+DROP TABLE IF EXISTS config_template_versions;
+DROP TABLE IF EXISTS config_templates;
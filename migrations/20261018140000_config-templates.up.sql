@@ -0,0 +1,28 @@
+-- Add up migration script here
+CREATE TABLE config_templates (
+    id TEXT PRIMARY KEY,  -- uuid V7
+    group_id TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+
+    UNIQUE (group_id, path)
+);
+
+CREATE TABLE config_template_versions (
+    id TEXT PRIMARY KEY,  -- uuid V7
+    template_id TEXT NOT NULL REFERENCES config_templates(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    UNIQUE (template_id, revision)
+);
+
+CREATE TABLE assembly_configs (
+    assembly_id TEXT NOT NULL REFERENCES assemblies(id) ON DELETE CASCADE,
+    template_version_id TEXT NOT NULL REFERENCES config_template_versions(id) ON DELETE RESTRICT,
+    PRIMARY KEY (assembly_id, template_version_id)
+);
+
+ALTER TABLE assemblies ADD COLUMN variables TEXT NOT NULL DEFAULT '{}'; -- json object
+
+CREATE INDEX idx_config_template_versions_template ON config_template_versions (template_id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE assemblies DROP COLUMN filename;
+ALTER TABLE assemblies DROP COLUMN layout;
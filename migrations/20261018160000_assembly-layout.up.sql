@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN layout TEXT NOT NULL DEFAULT 'flat';
+ALTER TABLE assemblies ADD COLUMN filename TEXT; -- NULL = default naming
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN format TEXT NOT NULL DEFAULT 'zip';
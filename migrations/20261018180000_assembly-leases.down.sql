@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_assemblies_queue;
+ALTER TABLE assemblies DROP COLUMN attempts;
+ALTER TABLE assemblies DROP COLUMN lease_expires_at;
+ALTER TABLE assemblies DROP COLUMN lease_id;
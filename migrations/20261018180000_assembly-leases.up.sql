@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN lease_id TEXT; -- uuid of the claim currently working on it
+ALTER TABLE assemblies ADD COLUMN lease_expires_at INTEGER; -- unix millis
+ALTER TABLE assemblies ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_assemblies_queue ON assemblies (status, id);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN progress TEXT; -- json, written by the worker holding the lease
@@ -0,0 +1,31 @@
+-- Add up migration script here
+CREATE TABLE group_webhooks (
+    id TEXT PRIMARY KEY NOT NULL,
+    group_id TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- hmac-sha256 key, only shown once when the webhook is created.
+    -- aes-256-gcm with associated data "{group_id}/.webhook/{id}"
+    secret_nonce BLOB NOT NULL,
+    secret_ciphertext BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX idx_group_webhooks_group ON group_webhooks (group_id);
+
+CREATE TABLE webhook_deliveries (
+    id TEXT PRIMARY KEY NOT NULL,
+    webhook_id TEXT NOT NULL REFERENCES group_webhooks(id) ON DELETE CASCADE,
+    assembly_id TEXT NOT NULL, -- no foreign key, the log outlives assembly cleanup
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL, -- json, signed and sent as is on every attempt
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, delivered, failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL, -- unix millis
+    response_status INTEGER,
+    error TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at);
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries (webhook_id, id);
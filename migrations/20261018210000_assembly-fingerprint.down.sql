@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_assemblies_fingerprint;
+ALTER TABLE assemblies DROP COLUMN fingerprint;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN fingerprint TEXT; -- sha256 of the resolved request, see assembler::fingerprint
+
+CREATE INDEX idx_assemblies_fingerprint ON assemblies (group_id, fingerprint);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_assemblies_release;
+ALTER TABLE assemblies DROP COLUMN release_pinned_at;
+ALTER TABLE assemblies DROP COLUMN release_name;
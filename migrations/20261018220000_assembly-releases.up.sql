@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE assemblies ADD COLUMN release_name TEXT; -- set while pinned, exempt from expiry
+ALTER TABLE assemblies ADD COLUMN release_pinned_at TEXT;
+
+CREATE UNIQUE INDEX idx_assemblies_release ON assemblies (group_id, release_name);
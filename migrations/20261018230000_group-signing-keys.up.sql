@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE group_signing_keys (
+    group_id TEXT PRIMARY KEY NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    public_key BLOB NOT NULL,
+    nonce BLOB NOT NULL,
+    ciphertext BLOB NOT NULL, -- ed25519 seed, aes-256-gcm with associated data "{group_id}/.signing-key"
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
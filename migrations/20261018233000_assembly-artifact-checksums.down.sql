@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE assembly_artifacts DROP COLUMN size_bytes;
+ALTER TABLE assembly_artifacts DROP COLUMN sha256;
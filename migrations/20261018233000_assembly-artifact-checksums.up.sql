@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- what was actually written for each artifact, set once the assembly is built
+ALTER TABLE assembly_artifacts ADD COLUMN sha256 TEXT;
+ALTER TABLE assembly_artifacts ADD COLUMN size_bytes INTEGER;
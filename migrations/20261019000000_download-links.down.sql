@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_download_links_expires;
+DROP INDEX IF EXISTS idx_download_links_group;
+DROP TABLE IF EXISTS download_links;
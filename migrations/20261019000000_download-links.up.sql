@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TABLE download_links (
+    id TEXT PRIMARY KEY NOT NULL, -- uuid v7
+    group_id TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    -- exactly one of the two is set
+    assembly_id TEXT REFERENCES assemblies(id) ON DELETE CASCADE,
+    artifact TEXT, -- group_id:artifact_id:version
+    created_by TEXT NOT NULL, -- user or machine key id
+    expires_at INTEGER NOT NULL, -- unix seconds, also part of the signed url
+    bound_ip TEXT,
+    max_uses INTEGER,
+    uses INTEGER NOT NULL DEFAULT 0,
+    revoked_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    CHECK ((assembly_id IS NULL) != (artifact IS NULL))
+);
+
+CREATE INDEX idx_download_links_group ON download_links (group_id, id);
+CREATE INDEX idx_download_links_expires ON download_links (expires_at);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_assembly_profile_runs_profile;
+DROP TABLE IF EXISTS assembly_profile_runs;
+DROP TABLE IF EXISTS assembly_profiles;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE assembly_profiles (
+    id TEXT PRIMARY KEY NOT NULL, -- uuid v7
+    group_id TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    artifacts TEXT NOT NULL, -- json array of group_id:artifact_id:version
+    options TEXT NOT NULL, -- json, same shape as an assemble request
+    created_by TEXT NOT NULL, -- user or machine key id
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+    UNIQUE (group_id, name)
+);
+
+CREATE TABLE assembly_profile_runs (
+    id TEXT PRIMARY KEY NOT NULL, -- uuid v7
+    profile_id TEXT NOT NULL REFERENCES assembly_profiles(id) ON DELETE CASCADE,
+    assembly_id TEXT NOT NULL, -- no foreign key, the history outlives assembly cleanup
+    requested_by TEXT NOT NULL,
+    reused INTEGER NOT NULL CHECK (reused IN (0, 1)),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX idx_assembly_profile_runs_profile ON assembly_profile_runs (profile_id, id);
@@ -0,0 +1,179 @@
+use std::io::{Read, Seek, Write};
+
+use chrono::{DateTime, Utc};
+use flate2::{Compression, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::assembler::errors::AssemblyError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    /// json list of download links, no archive is built
+    #[serde(rename = "manifest")]
+    Manifest,
+}
+
+impl ArchiveFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::Manifest => "manifest",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+            ArchiveFormat::TarZst => "application/zstd",
+            ArchiveFormat::Manifest => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::Manifest => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = AssemblyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zip" => Ok(Self::Zip),
+            "tar.gz" => Ok(Self::TarGz),
+            "tar.zst" => Ok(Self::TarZst),
+            "manifest" => Ok(Self::Manifest),
+            other => Err(AssemblyError::Internal(format!(
+                "unknown archive format {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestEntry {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub url: String,
+}
+
+/// written into every archive, so a server can report which plugin set it runs
+pub const EMBEDDED_MANIFEST_PATH: &str = "cogere-manifest.json";
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddedManifest {
+    pub assembly_id: Uuid,
+    pub group_id: Uuid,
+    pub requested_at: DateTime<Utc>,
+    pub cogere_version: &'static str,
+    pub cogere_git_sha: &'static str,
+    pub artifacts: Vec<EmbeddedArtifact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddedArtifact {
+    /// `group_id:artifact_id:version`
+    pub coordinate: String,
+    pub path: String,
+    /// `local` or `external:<provider>:<id>`
+    pub source: String,
+    pub sha256: String,
+}
+
+/// writes entries straight through to `W`, nothing is buffered beyond the compressor state
+pub enum ArchiveWriter<W: Write + Seek> {
+    // boxed, the zip writer is a lot bigger than the tar builders
+    Zip(Box<zip::ZipWriter<W>>),
+    TarGz(tar::Builder<GzEncoder<W>>),
+    TarZst(tar::Builder<zstd::Encoder<'static, W>>),
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    pub fn new(format: ArchiveFormat, out: W) -> Result<Self, AssemblyError> {
+        Ok(match format {
+            ArchiveFormat::Zip => Self::Zip(Box::new(zip::ZipWriter::new(out))),
+            ArchiveFormat::TarGz => Self::TarGz(tar::Builder::new(GzEncoder::new(
+                out,
+                Compression::default(),
+            ))),
+            ArchiveFormat::TarZst => Self::TarZst(tar::Builder::new(zstd::Encoder::new(
+                out,
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?)),
+            ArchiveFormat::Manifest => {
+                return Err(AssemblyError::Internal(
+                    "manifest assemblies have no archive".into(),
+                ));
+            }
+        })
+    }
+
+    /// copies `size` bytes from `data` into a new entry at `path`. entry metadata is fixed,
+    /// so the same entries in the same order always produce the same bytes.
+    pub fn add<R: Read>(
+        &mut self,
+        path: &str,
+        mut data: R,
+        size: u64,
+    ) -> Result<(), AssemblyError> {
+        match self {
+            ArchiveWriter::Zip(zip) => {
+                let options = zip::write::FileOptions::<()>::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .last_modified_time(zip::DateTime::default())
+                    .unix_permissions(0o644)
+                    .large_file(size >= u32::MAX as u64);
+                zip.start_file(path, options)?;
+                std::io::copy(&mut data, zip)?;
+            }
+            ArchiveWriter::TarGz(tar) => append_tar(tar, path, data, size)?,
+            ArchiveWriter::TarZst(tar) => append_tar(tar, path, data, size)?,
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<W, AssemblyError> {
+        Ok(match self {
+            ArchiveWriter::Zip(zip) => zip.finish()?,
+            ArchiveWriter::TarGz(tar) => tar.into_inner()?.finish()?,
+            ArchiveWriter::TarZst(tar) => tar.into_inner()?.finish()?,
+        })
+    }
+}
+
+fn append_tar<W: Write, R: Read>(
+    tar: &mut tar::Builder<W>,
+    path: &str,
+    data: R,
+    size: u64,
+) -> Result<(), AssemblyError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_entry_type(tar::EntryType::Regular);
+    tar.append_data(&mut header, path, data)?;
+    Ok(())
+}
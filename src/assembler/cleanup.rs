@@ -5,7 +5,10 @@ use sqlx::SqlitePool;
 use tokio::sync::RwLock;
 
 use crate::{
-    database::assembly::{cleanup_expired_assemblies, cleanup_old_assemblies},
+    database::{
+        assembly::{cleanup_expired_assemblies, cleanup_old_assemblies},
+        links::cleanup_expired_links,
+    },
     models::settings::InstanceSettings,
     storage::filesystem::FilesystemStorage,
 };
@@ -32,6 +35,10 @@ pub async fn run(
             tracing::error!(error = %e, "old assembly cleanup failed");
         }
 
+        if let Err(e) = cleanup_expired_links(&pool, Duration::days(max_age_days)).await {
+            tracing::error!(error = %e, "download link cleanup failed");
+        }
+
         tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)).await;
     }
 }
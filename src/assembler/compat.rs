@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+
+use crate::{
+    assembler::{ArtifactCoordinate, errors::AssemblyError},
+    models::assembly::ResolvedArtifact,
+};
+
+/// rejects picks whose api-version is newer than the target, or that declare
+/// supported game versions which don't include it. all offenders are reported at once.
+pub fn check_minecraft_version(
+    target: &str,
+    resolved: &[(ArtifactCoordinate, ResolvedArtifact)],
+) -> Result<(), AssemblyError> {
+    let incompatible: Vec<String> = resolved
+        .iter()
+        .filter_map(|(coord, artifact)| {
+            let version = &artifact.version;
+
+            if let Some(api_version) = &version.api_version
+                && compare_versions(api_version, target) == Some(Ordering::Greater)
+            {
+                return Some(format!("{coord} (api-version {api_version})"));
+            }
+
+            let supported = version.game_versions.is_empty()
+                || version
+                    .game_versions
+                    .iter()
+                    .any(|declared| game_version_matches(declared, target));
+
+            (!supported).then(|| format!("{coord} (supports {})", version.game_versions.join(", ")))
+        })
+        .collect();
+
+    if incompatible.is_empty() {
+        Ok(())
+    } else {
+        Err(AssemblyError::Incompatible(format!(
+            "minecraft {target}: {}",
+            incompatible.join(", ")
+        )))
+    }
+}
+
+/// compares dotted numeric versions, missing components count as 0 so 1.21 == 1.21.0.
+/// returns None if either side isn't a plain release version (snapshots etc).
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let parse = |s: &str| {
+        s.split('.')
+            .map(|part| part.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()
+    };
+    let (a, b) = (parse(a)?, parse(b)?);
+
+    let len = a.len().max(b.len());
+    let component = |v: &[u32], i: usize| v.get(i).copied().unwrap_or(0);
+
+    Some(
+        (0..len)
+            .map(|i| component(&a, i).cmp(&component(&b, i)))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal),
+    )
+}
+
+/// `1.21.4` only matches itself, `1.21.x` (or `1.21.*`) matches 1.21 and every 1.21 patch.
+fn game_version_matches(declared: &str, target: &str) -> bool {
+    match declared
+        .strip_suffix(".x")
+        .or_else(|| declared.strip_suffix(".*"))
+    {
+        Some(prefix) => {
+            target == prefix
+                || target
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        }
+        None => compare_versions(declared, target)
+            .map(|o| o.is_eq())
+            .unwrap_or(declared == target),
+    }
+}
+
+/// rejects jars whose class files target a newer java release than the servers run.
+/// jars with an unknown target are let through.
+pub fn check_java_version(
+    target: u16,
+    jars: &[(ArtifactCoordinate, Option<u16>)],
+) -> Result<(), AssemblyError> {
+    let incompatible: Vec<String> = jars
+        .iter()
+        .filter_map(|(coord, required)| {
+            required
+                .filter(|required| *required > target)
+                .map(|required| format!("{coord} (requires java {required})"))
+        })
+        .collect();
+
+    if incompatible.is_empty() {
+        Ok(())
+    } else {
+        Err(AssemblyError::Incompatible(format!(
+            "java {target}: {}",
+            incompatible.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_components_count_as_zero() {
+        assert_eq!(compare_versions("1.20", "1.20.1"), Some(Ordering::Less));
+        assert_eq!(compare_versions("1.20.1", "1.20"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("1.21", "1.21.0"), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn components_compare_numerically() {
+        assert_eq!(compare_versions("1.9", "1.10"), Some(Ordering::Less));
+        assert_eq!(compare_versions("1.2", "1.20"), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn non_release_versions_are_incomparable() {
+        assert_eq!(compare_versions("1.21-pre1", "1.21"), None);
+        assert_eq!(compare_versions("1.21", "24w14a"), None);
+    }
+
+    #[test]
+    fn exact_game_version() {
+        assert!(game_version_matches("1.20.1", "1.20.1"));
+        assert!(game_version_matches("1.21", "1.21.0"));
+        assert!(!game_version_matches("1.20", "1.20.1"));
+        assert!(game_version_matches("24w14a", "24w14a"));
+    }
+
+    #[test]
+    fn wildcard_game_version() {
+        for declared in ["1.21.x", "1.21.*"] {
+            assert!(game_version_matches(declared, "1.21"));
+            assert!(game_version_matches(declared, "1.21.4"));
+            assert!(!game_version_matches(declared, "1.210"));
+            assert!(!game_version_matches(declared, "1.22"));
+            assert!(!game_version_matches(declared, "1.2"));
+        }
+    }
+}
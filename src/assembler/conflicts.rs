@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    assembler::{ArtifactCoordinate, errors::AssemblyError},
+    plugins::descriptor::PluginDescriptor,
+};
+
+/// paper refuses to load two plugins with the same name, and two plugins sharing
+/// a main class will clash on the classpath. both are checked across the whole assembly.
+pub fn check_conflicts(
+    plugins: &[(ArtifactCoordinate, PluginDescriptor)],
+) -> Result<(), AssemblyError> {
+    let mut by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut by_main: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (coord, descriptor) in plugins {
+        by_name
+            .entry(descriptor.name.to_lowercase())
+            .or_default()
+            .push(coord.to_string());
+        by_main
+            .entry(descriptor.main.clone())
+            .or_default()
+            .push(coord.to_string());
+    }
+
+    let conflicts: Vec<String> = by_name
+        .iter()
+        .filter(|(_, coords)| coords.len() > 1)
+        .map(|(name, coords)| format!("plugin name '{name}' in {}", coords.join(", ")))
+        .chain(
+            by_main
+                .iter()
+                .filter(|(_, coords)| coords.len() > 1)
+                .map(|(main, coords)| format!("main class '{main}' in {}", coords.join(", "))),
+        )
+        .collect();
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(AssemblyError::ConflictingPlugins(conflicts.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(artifact_id: &str, name: &str, main: &str) -> (ArtifactCoordinate, PluginDescriptor) {
+        (
+            ArtifactCoordinate {
+                group_id: "com.example".into(),
+                artifact_id: artifact_id.into(),
+                version: "1.0.0".into(),
+            },
+            PluginDescriptor {
+                name: name.into(),
+                main: main.into(),
+                api_version: None,
+            },
+        )
+    }
+
+    #[test]
+    fn distinct_plugins_pass() {
+        let plugins = [plugin("a", "A", "a.A"), plugin("b", "B", "b.B")];
+        assert!(check_conflicts(&plugins).is_ok());
+    }
+
+    #[test]
+    fn names_clash_ignoring_case() {
+        let plugins = [plugin("a", "Example", "a.A"), plugin("b", "example", "b.B")];
+        let err = check_conflicts(&plugins).unwrap_err().to_string();
+        assert!(err.contains("plugin name 'example'"), "{err}");
+        assert!(
+            err.contains("com.example:a:1.0.0, com.example:b:1.0.0"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn main_classes_clash() {
+        let plugins = [
+            plugin("a", "A", "shared.Main"),
+            plugin("b", "B", "shared.Main"),
+        ];
+        let err = check_conflicts(&plugins).unwrap_err().to_string();
+        assert!(err.contains("main class 'shared.Main'"), "{err}");
+        assert!(!err.contains("plugin name"), "{err}");
+    }
+
+    #[test]
+    fn reports_every_conflict() {
+        let plugins = [
+            plugin("a", "A", "shared.Main"),
+            plugin("b", "A", "shared.Main"),
+        ];
+        let err = check_conflicts(&plugins).unwrap_err().to_string();
+        assert!(
+            err.contains("plugin name 'a'") && err.contains("main class"),
+            "{err}"
+        );
+    }
+}
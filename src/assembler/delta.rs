@@ -0,0 +1,169 @@
+use std::{
+    collections::BTreeSet,
+    io::{BufWriter, Seek, Write},
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    assembler::{
+        archive::{ArchiveFormat, ArchiveWriter},
+        errors::AssemblyError,
+        layout::jar_path,
+        worker::hash_reader,
+    },
+    auth::extractor::AuthenticatedEntity,
+    database,
+    errors::Error,
+    server::AppState,
+    storage::{StorageError, filesystem::TempFile},
+};
+
+/// written into every delta, next to the jars it ships
+pub const DELTA_MANIFEST_PATH: &str = "cogere-delta.json";
+
+#[derive(Debug, Serialize)]
+pub struct DeltaManifest {
+    pub assembly_id: Uuid,
+    pub group_id: Uuid,
+    /// shipped in this delta
+    pub add: Vec<DeltaEntry>,
+    /// already installed, left as they are
+    pub keep: Vec<DeltaEntry>,
+    /// installed jars that aren't part of the assembly, by sha256
+    pub remove: Vec<String>,
+}
+
+/// a built delta, still in its temp file so it can be streamed out
+pub struct Delta {
+    /// rewound to the start
+    pub file: TempFile,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeltaEntry {
+    /// `group_id:artifact_id:version`
+    pub coordinate: String,
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// a zip of only the jars of a completed assembly that aren't in `installed`, plus a
+/// `cogere-delta.json` saying what to keep and what to remove. configs aren't included,
+/// they come with the full download.
+pub async fn build_delta(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    assembly_id: Uuid,
+    installed: Vec<String>,
+) -> Result<Delta, Error> {
+    super::require_assembly_access(state, entity, group_id, assembly_id).await?;
+
+    let assembly = database::assembly::get_assembly(&state.db, group_id, assembly_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("assembly {assembly_id} not found")))?;
+
+    if assembly.status != "completed" {
+        return Err(Error::BadRequest("assembly not ready yet".into()));
+    }
+
+    let installed: BTreeSet<String> = installed
+        .into_iter()
+        .map(|sha256| sha256.to_ascii_lowercase())
+        .collect();
+
+    let mut add = Vec::new();
+    let mut keep = Vec::new();
+    let mut blobs = Vec::new();
+    let mut wanted = BTreeSet::new();
+
+    for artifact in database::assembly::get_built_artifacts(&state.db, assembly_id).await? {
+        let coord = artifact.coordinate;
+        let resolved = database::assembly::get_artifact(&state.db, &coord, group_id).await?;
+        let blob_id = resolved
+            .version
+            .blob_id
+            .ok_or_else(|| Error::NotFound(format!("{coord} is not cached")))?;
+        let blob = database::blobs::get_blob(&state.db, blob_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("blob {blob_id} not found")))?;
+
+        // the jar was replaced after the build, a delta would no longer match the assembly
+        if artifact
+            .sha256
+            .as_ref()
+            .is_some_and(|sha256| *sha256 != blob.sha256)
+        {
+            return Err(Error::Conflict(format!(
+                "{coord} changed since the assembly was built, request a new assembly"
+            )));
+        }
+
+        let entry = DeltaEntry {
+            coordinate: coord.to_string(),
+            path: jar_path(assembly.layout, assembly.filename.as_deref(), &coord)?,
+            sha256: blob.sha256.clone(),
+            size_bytes: blob.size_bytes,
+        };
+        wanted.insert(blob.sha256);
+
+        if installed.contains(&entry.sha256) {
+            keep.push(entry);
+        } else {
+            blobs.push(blob_id);
+            add.push(entry);
+        }
+    }
+
+    let manifest = DeltaManifest {
+        assembly_id,
+        group_id,
+        add,
+        keep,
+        remove: installed.difference(&wanted).cloned().collect(),
+    };
+
+    tracing::info!(
+        assembly_id = %assembly_id,
+        add = manifest.add.len(),
+        remove = manifest.remove.len(),
+        "delta built"
+    );
+
+    let mut temp = state.storage.temp_file().await?;
+    let storage = state.storage.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut archive =
+            ArchiveWriter::new(ArchiveFormat::Zip, BufWriter::new(temp.file.try_clone()?))?;
+
+        for (entry, blob_id) in manifest.add.iter().zip(blobs) {
+            let data = storage.open_blocking(blob_id).map_err(|e| match e {
+                StorageError::NotFound(_) => AssemblyError::ArtifactNotFound(blob_id.to_string()),
+                e => AssemblyError::Storage(e),
+            })?;
+            archive.add(&entry.path, data, entry.size_bytes)?;
+        }
+
+        let json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| AssemblyError::Internal(e.to_string()))?;
+        archive.add(DELTA_MANIFEST_PATH, json.as_slice(), json.len() as u64)?;
+        archive.finish()?.flush()?;
+
+        temp.file.rewind()?;
+        let (sha256, size_bytes) = hash_reader(&mut temp.file)?;
+        temp.file.rewind()?;
+        Ok::<_, AssemblyError>(Delta {
+            file: temp,
+            sha256,
+            size_bytes,
+        })
+    })
+    .await?
+    .map_err(Error::from)
+}
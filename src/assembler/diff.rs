@@ -0,0 +1,120 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    assembler::compat::compare_versions,
+    auth::extractor::AuthenticatedEntity,
+    database,
+    errors::Error,
+    models::assembly::{AssemblyDiff, BuiltArtifact, ChangedArtifact},
+    server::AppState,
+};
+
+pub async fn diff_assemblies(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    from: Uuid,
+    to: Uuid,
+) -> Result<AssemblyDiff, Error> {
+    let mut artifacts = Vec::with_capacity(2);
+    for id in [from, to] {
+        super::require_assembly_access(state, entity, group_id, id).await?;
+
+        if database::assembly::get_assembly(&state.db, group_id, id)
+            .await?
+            .is_none()
+        {
+            return Err(Error::NotFound(format!("assembly {id} not found")));
+        }
+
+        artifacts.push(database::assembly::get_built_artifacts(&state.db, id).await?);
+    }
+
+    let new = artifacts.pop().unwrap_or_default();
+    let old = artifacts.pop().unwrap_or_default();
+
+    Ok(diff(from, to, old, new))
+}
+
+/// matches artifacts by coordinate first, then pairs what's left by plugin, so an assembly
+/// that holds two versions of one plugin still diffs sensibly
+pub fn diff(
+    from: Uuid,
+    to: Uuid,
+    old: Vec<BuiltArtifact>,
+    new: Vec<BuiltArtifact>,
+) -> AssemblyDiff {
+    let mut result = AssemblyDiff {
+        from,
+        to,
+        added: Vec::new(),
+        removed: Vec::new(),
+        upgraded: Vec::new(),
+        rebuilt: Vec::new(),
+        unchanged: 0,
+    };
+
+    let key = |a: &BuiltArtifact| {
+        (
+            a.coordinate.group_id.clone(),
+            a.coordinate.artifact_id.clone(),
+        )
+    };
+
+    let mut remaining_old: BTreeMap<_, VecDeque<BuiltArtifact>> = BTreeMap::new();
+    for artifact in old {
+        remaining_old
+            .entry(key(&artifact))
+            .or_default()
+            .push_back(artifact);
+    }
+
+    let mut remaining_new = Vec::new();
+    for artifact in new {
+        let versions = remaining_old.entry(key(&artifact)).or_default();
+        let same = versions
+            .iter()
+            .position(|o| o.coordinate.version == artifact.coordinate.version);
+
+        match same.and_then(|i| versions.remove(i)) {
+            // older assemblies have no checksums, they can't be told apart
+            Some(old) => match (&old.sha256, &artifact.sha256) {
+                (Some(a), Some(b)) if a != b => result.rebuilt.push(ChangedArtifact {
+                    old,
+                    new: artifact,
+                    downgrade: false,
+                }),
+                _ => result.unchanged += 1,
+            },
+            None => remaining_new.push(artifact),
+        }
+    }
+
+    for artifact in remaining_new {
+        match remaining_old
+            .get_mut(&key(&artifact))
+            .and_then(|versions| versions.pop_front())
+        {
+            Some(old) => {
+                let downgrade =
+                    compare_versions(&artifact.coordinate.version, &old.coordinate.version)
+                        == Some(Ordering::Less);
+                result.upgraded.push(ChangedArtifact {
+                    old,
+                    new: artifact,
+                    downgrade,
+                });
+            }
+            None => result.added.push(artifact),
+        }
+    }
+
+    result.removed = remaining_old.into_values().flatten().collect();
+
+    result
+}
@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-use crate::storage::StorageError;
+use crate::{secrets::SecretError, storage::StorageError};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AssemblyError {
@@ -8,8 +8,6 @@ pub enum AssemblyError {
     ArtifactNotFound(String),
     #[error("assembly timed out after {0}s")]
     Timeout(u64),
-    #[error("queue unavailable")]
-    QueueUnavailable,
     #[error("unsupported external provider: {0}")]
     UnsupportedProvider(String),
     #[error("failed to fetch external artifact: {0}")]
@@ -26,6 +24,20 @@ pub enum AssemblyError {
     Internal(String),
     #[error("no artifacts provided")]
     NoArtifacts,
+    #[error("invalid plugin {0}: {1}")]
+    InvalidPlugin(String, String),
+    #[error("conflicting plugins: {0}")]
+    ConflictingPlugins(String),
+    #[error("incompatible with {0}")]
+    Incompatible(String),
+    #[error("invalid config {0}: {1}")]
+    InvalidConfig(String, String),
+    #[error(transparent)]
+    Secret(#[from] SecretError),
+    #[error("invalid layout: {0}")]
+    InvalidLayout(String),
+    #[error("assembly abandoned")]
+    Abandoned,
 }
 
 impl Serialize for AssemblyError {
@@ -0,0 +1,33 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// wakes event streams of an assembly when a build in this process changes it. a build in a
+/// standalone worker can't reach them, so streams still look at the database now and then.
+#[derive(Debug, Clone, Default)]
+pub struct AssemblyWatchers(Arc<Mutex<HashMap<Uuid, watch::Sender<()>>>>);
+
+impl AssemblyWatchers {
+    pub fn subscribe(&self, id: Uuid) -> watch::Receiver<()> {
+        let mut watchers = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        // streams that went away leave their sender behind
+        watchers.retain(|_, tx| tx.receiver_count() > 0);
+        watchers
+            .entry(id)
+            .or_insert_with(|| watch::channel(()).0)
+            .subscribe()
+    }
+
+    pub fn notify(&self, id: Uuid) {
+        let mut watchers = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(tx) = watchers.get(&id)
+            && tx.send(()).is_err()
+        {
+            watchers.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    assembler::{ArtifactCoordinate, AssemblyOptions},
+    configs::referenced_secrets,
+    models::{assembly::ResolvedArtifact, configs::ConfigTemplateVersion, secrets::GroupSecret},
+};
+
+/// everything that decides what ends up in an assembly. it's hashed as json, so every list
+/// is sorted and maps are `BTreeMap`s.
+#[derive(Serialize)]
+struct Inputs<'a> {
+    group_id: Uuid,
+    /// coordinate, plugin version and cached blob
+    artifacts: Vec<(String, Uuid, Option<Uuid>)>,
+    options: AssemblyOptions,
+    /// pinned template revisions
+    configs: Vec<Uuid>,
+    /// a changed secret changes the rendered configs, only the referenced ones matter
+    secrets: Vec<(&'a str, DateTime<Utc>)>,
+}
+
+/// two requests with the same fingerprint build the same archive, apart from the embedded
+/// manifest naming its assembly, so a finished assembly can be handed out again instead of
+/// rebuilt
+pub fn fingerprint(
+    group_id: Uuid,
+    resolved: &[(ArtifactCoordinate, ResolvedArtifact)],
+    options: &AssemblyOptions,
+    configs: &[ConfigTemplateVersion],
+    secrets: &[GroupSecret],
+) -> String {
+    let mut artifacts: Vec<_> = resolved
+        .iter()
+        .map(|(coord, artifact)| {
+            (
+                coord.to_string(),
+                artifact.version.id,
+                artifact.version.blob_id,
+            )
+        })
+        .collect();
+    artifacts.sort();
+
+    // configs are written in path order whatever order they were asked for in
+    let mut options = options.clone();
+    options.configs.sort();
+
+    let referenced: BTreeSet<String> = configs
+        .iter()
+        .flat_map(|v| referenced_secrets(&v.content))
+        .collect();
+    let mut secrets: Vec<_> = secrets
+        .iter()
+        .filter(|s| referenced.contains(&s.name))
+        .map(|s| (s.name.as_str(), s.updated_at))
+        .collect();
+    secrets.sort();
+
+    let mut configs: Vec<_> = configs.iter().map(|v| v.id).collect();
+    configs.sort();
+
+    let inputs = Inputs {
+        group_id,
+        artifacts,
+        options,
+        configs,
+        secrets,
+    };
+
+    // serializing plain structs, maps and strings can't fail
+    let json = serde_json::to_vec(&inputs).unwrap_or_default();
+    format!("{:x}", Sha256::digest(&json))
+}
@@ -1,10 +1,11 @@
 use uuid::Uuid;
 
-use crate::assembler::ArtifactCoordinate;
+use crate::assembler::{ArtifactCoordinate, AssemblyOptions};
 
 #[derive(Clone)]
 pub struct AssemblyJob {
     pub id: Uuid,
     pub group_id: Uuid,
     pub artifacts: Vec<ArtifactCoordinate>,
+    pub options: AssemblyOptions,
 }
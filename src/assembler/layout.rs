@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assembler::{ArtifactCoordinate, errors::AssemblyError};
+
+/// the historical `{group_id}.{artifact_id}-{version}.jar` naming
+pub const DEFAULT_FILENAME: &str = "{group_id}.{artifact_id}-{version}.jar";
+
+/// where jars land inside the assembly. config templates already carry their path relative to
+/// the server root (`plugins/LuckPerms/config.yml`, `config/sodium.json`) and are not moved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Flat,
+    Paper,
+    Fabric,
+}
+
+impl Layout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::Flat => "flat",
+            Layout::Paper => "paper",
+            Layout::Fabric => "fabric",
+        }
+    }
+
+    fn jar_dir(&self) -> &'static str {
+        match self {
+            Layout::Flat => "",
+            Layout::Paper => "plugins/",
+            Layout::Fabric => "mods/",
+        }
+    }
+}
+
+impl std::str::FromStr for Layout {
+    type Err = AssemblyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Self::Flat),
+            "paper" => Ok(Self::Paper),
+            "fabric" => Ok(Self::Fabric),
+            other => Err(AssemblyError::InvalidLayout(format!(
+                "unknown layout {other}"
+            ))),
+        }
+    }
+}
+
+/// expands `{group_id}`, `{artifact_id}` and `{version}` in the filename template and places
+/// the result in the layout's jar directory.
+pub fn jar_path(
+    layout: Layout,
+    filename: Option<&str>,
+    coord: &ArtifactCoordinate,
+) -> Result<String, AssemblyError> {
+    let template = filename.unwrap_or(DEFAULT_FILENAME);
+    let mut name = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| AssemblyError::InvalidLayout("unterminated placeholder".into()))?;
+
+        match &after[..end] {
+            "group_id" => name.push_str(&coord.group_id),
+            "artifact_id" => name.push_str(&coord.artifact_id),
+            "version" => name.push_str(&coord.version),
+            other => {
+                return Err(AssemblyError::InvalidLayout(format!(
+                    "unknown placeholder {{{other}}}"
+                )));
+            }
+        }
+
+        rest = &after[end + 1..];
+    }
+    name.push_str(rest);
+
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(AssemblyError::InvalidLayout(format!(
+            "invalid filename '{name}' for {coord}"
+        )));
+    }
+
+    Ok(format!("{}{name}", layout.jar_dir()))
+}
+
+/// every entry must have its own path, otherwise later files would silently replace earlier ones
+pub fn check_unique_paths<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Result<(), AssemblyError> {
+    let mut seen = BTreeSet::new();
+    let duplicates: BTreeSet<&str> = paths.into_iter().filter(|p| !seen.insert(*p)).collect();
+
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(AssemblyError::InvalidLayout(format!(
+            "duplicate paths: {}",
+            duplicates.into_iter().collect::<Vec<_>>().join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coord() -> ArtifactCoordinate {
+        ArtifactCoordinate {
+            group_id: "net.luckperms".into(),
+            artifact_id: "luckperms".into(),
+            version: "5.4.0".into(),
+        }
+    }
+
+    #[test]
+    fn default_filename_per_layout() {
+        let path = |layout| jar_path(layout, None, &coord()).unwrap();
+        assert_eq!(path(Layout::Flat), "net.luckperms.luckperms-5.4.0.jar");
+        assert_eq!(
+            path(Layout::Paper),
+            "plugins/net.luckperms.luckperms-5.4.0.jar"
+        );
+        assert_eq!(
+            path(Layout::Fabric),
+            "mods/net.luckperms.luckperms-5.4.0.jar"
+        );
+    }
+
+    #[test]
+    fn custom_filename() {
+        let path = jar_path(Layout::Paper, Some("{artifact_id}.jar"), &coord()).unwrap();
+        assert_eq!(path, "plugins/luckperms.jar");
+    }
+
+    #[test]
+    fn rejects_unknown_and_unterminated_placeholders() {
+        assert!(jar_path(Layout::Flat, Some("{name}.jar"), &coord()).is_err());
+        assert!(jar_path(Layout::Flat, Some("{artifact_id.jar"), &coord()).is_err());
+    }
+
+    #[test]
+    fn rejects_filenames_that_leave_the_directory() {
+        for filename in ["../{artifact_id}.jar", "a/b.jar", "a\\b.jar", "..", ""] {
+            assert!(jar_path(Layout::Paper, Some(filename), &coord()).is_err());
+        }
+    }
+}
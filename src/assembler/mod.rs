@@ -1,20 +1,34 @@
-use serde::Serialize;
+use core::fmt;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    assembler::{errors::AssemblyError, job::AssemblyJob},
+    assembler::{archive::ArchiveFormat, errors::AssemblyError, layout::Layout},
     auth::{
         extractor::AuthenticatedEntity,
         permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
     },
-    database,
+    configs, database,
     errors::Error,
+    models::configs::ConfigTemplateVersion,
     server::AppState,
 };
 
+pub mod archive;
 pub mod cleanup;
+pub mod compat;
+pub mod conflicts;
+pub mod delta;
+pub mod diff;
 pub mod errors;
+pub mod events;
+pub mod fingerprint;
 pub mod job;
+pub mod layout;
+pub mod profiles;
+pub mod releases;
 pub mod worker;
 
 #[derive(Clone, Debug, Serialize)]
@@ -24,10 +38,35 @@ pub struct ArtifactCoordinate {
     pub version: String,
 }
 
+impl fmt::Display for ArtifactCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.version)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssemblyOptions {
+    /// picks that don't support this version are rejected
+    pub minecraft_version: Option<String>,
+    /// jars targeting a newer java release than this are rejected
+    pub java_version: Option<u16>,
+    /// config template paths, pinned to their latest revision at request time
+    pub configs: Vec<String>,
+    /// values for `{{ name }}` placeholders in the config templates
+    pub variables: BTreeMap<String, String>,
+    pub layout: Layout,
+    /// jar filename template, see `layout::jar_path`
+    pub filename: Option<String>,
+    pub format: ArchiveFormat,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RequestAssemblyResponse {
     pub id: Uuid,
     pub status_uri: String,
+    /// an identical completed assembly was handed out instead of queueing a new one
+    pub reused: bool,
 }
 
 pub async fn request_assembly(
@@ -35,29 +74,222 @@ pub async fn request_assembly(
     entity: &AuthenticatedEntity,
     group_id: Uuid,
     artifacts: Vec<ArtifactCoordinate>,
+    options: AssemblyOptions,
 ) -> Result<RequestAssemblyResponse, Error> {
-    PermissionChecker::new(&state.db, &entity)
-        .require(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
-        .await?;
+    // keys can be limited to single plugins, those are checked once resolved
+    let checker = PermissionChecker::new(&state.db, entity);
+    if !checker
+        .can_any(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?
+    {
+        return Err(Error::Forbidden);
+    }
 
     if artifacts.len() == 0 {
         return Err(AssemblyError::NoArtifacts.into());
     }
 
-    // TODO CHECK FOR PER ARTIFACT PERMISSION
+    let mut resolved = Vec::with_capacity(artifacts.len());
+    let mut not_found = Vec::new();
+    let mut denied = Vec::new();
+    for coord in &artifacts {
+        let artifact = match database::assembly::get_artifact(&state.db, coord, group_id).await {
+            Ok(artifact) => artifact,
+            Err(AssemblyError::ArtifactNotFound(_)) => {
+                not_found.push(coord.to_string());
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if !checker
+            .can(
+                PermissionCheck::new(ResourceType::Artifact, Action::Get)
+                    .in_group(group_id)
+                    .with_resource_id(artifact.plugin.id),
+            )
+            .await?
+        {
+            denied.push(coord.to_string());
+        }
+        resolved.push((coord.clone(), artifact));
+    }
 
-    let id = database::assembly::create_assembly(&state.db, group_id, artifacts.clone()).await?;
+    // everything wrong with the request at once, not just the first bad coordinate
+    let mut problems = Vec::new();
+    if !denied.is_empty() {
+        problems.push(format!("not allowed to assemble {}", denied.join(", ")));
+    }
+    if !not_found.is_empty() {
+        problems.push(format!("not found: {}", not_found.join(", ")));
+    }
+    match (denied.is_empty(), not_found.is_empty()) {
+        (true, true) => {}
+        (true, false) => return Err(Error::NotFound(problems.join("; "))),
+        (false, _) => return Err(Error::NotAllowed(problems.join("; "))),
+    }
 
-    state
-        .assembly_tx
-        .send(AssemblyJob {
-            id,
-            group_id,
-            artifacts,
-        })
-        .await
-        .map_err(|_| AssemblyError::QueueUnavailable)?;
+    // manifest entries point at the plugin download, which only serves cached jars
+    if options.format == ArchiveFormat::Manifest {
+        let uncached: Vec<String> = resolved
+            .iter()
+            .filter(|(_, artifact)| !artifact.version.is_cached())
+            .map(|(coord, _)| coord.to_string())
+            .collect();
+        if !uncached.is_empty() {
+            return Err(Error::BadRequest(format!(
+                "manifest assemblies need cached plugins, not cached: {}",
+                uncached.join(", ")
+            )));
+        }
+    }
 
+    if let Some(target) = &options.minecraft_version {
+        compat::check_minecraft_version(target, &resolved)?;
+    }
+
+    if let Some(target) = options.java_version {
+        let targets: Vec<_> = resolved
+            .iter()
+            .map(|(coord, artifact)| (coord.clone(), artifact.version.min_java_version))
+            .collect();
+        compat::check_java_version(target, &targets)?;
+    }
+
+    let secrets = if options.configs.is_empty() {
+        Vec::new()
+    } else {
+        database::secrets::list_secrets(&state.db, group_id).await?
+    };
+    // names only, values stay encrypted until the worker renders the configs
+    let secret_names: BTreeMap<String, String> = secrets
+        .iter()
+        .map(|s| (s.name.clone(), String::new()))
+        .collect();
+
+    // rendered configs may hold secrets, they only ever travel inside an archive
+    if options.format == ArchiveFormat::Manifest && !options.configs.is_empty() {
+        return Err(Error::BadRequest(
+            "manifest assemblies can't include configs".into(),
+        ));
+    }
+
+    let mut config_versions: Vec<ConfigTemplateVersion> = Vec::with_capacity(options.configs.len());
+    for path in &options.configs {
+        if config_versions.iter().any(|v| &v.path == path) {
+            return Err(Error::BadRequest(format!("config {path} requested twice")));
+        }
+
+        let version = database::configs::get_config_version(&state.db, group_id, path, None)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("config {path} not found")))?;
+
+        // render once up front so missing variables fail the request instead of the job
+        configs::render(&version.content, &options.variables, &secret_names)
+            .map_err(|e| AssemblyError::InvalidConfig(path.clone(), e.to_string()))?;
+
+        config_versions.push(version);
+    }
+
+    // the archive carries rendered secret values, so it's only for those who may manage them
+    if config_versions
+        .iter()
+        .any(|v| !configs::referenced_secrets(&v.content).is_empty())
+    {
+        checker
+            .require(PermissionCheck::new(ResourceType::Secret, Action::Manage).in_group(group_id))
+            .await?;
+    }
+
+    let jar_paths = artifacts
+        .iter()
+        .map(|coord| layout::jar_path(options.layout, options.filename.as_deref(), coord))
+        .collect::<Result<Vec<_>, _>>()?;
+    let embedded_manifest =
+        (options.format != ArchiveFormat::Manifest).then_some(archive::EMBEDDED_MANIFEST_PATH);
+    layout::check_unique_paths(
+        jar_paths
+            .iter()
+            .map(String::as_str)
+            .chain(config_versions.iter().map(|v| v.path.as_str()))
+            .chain(embedded_manifest),
+    )?;
+
+    let fingerprint =
+        fingerprint::fingerprint(group_id, &resolved, &options, &config_versions, &secrets);
+
+    let expiry_secs = state.settings.read().await.assembly_expiry_secs;
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expiry_secs as i64);
+    if let Some(id) =
+        database::assembly::find_reusable_assembly(&state.db, group_id, &fingerprint, expires_at)
+            .await?
+    {
+        tracing::info!(group_id = %group_id, assembly_id = %id, "reusing completed assembly");
+        return response(state, group_id, id, true);
+    }
+
+    let id = database::assembly::create_assembly(
+        &state.db,
+        group_id,
+        artifacts,
+        &options,
+        &config_versions,
+        &fingerprint,
+    )
+    .await?;
+
+    state.assembly_notify.notify_one();
+
+    response(state, group_id, id, false)
+}
+
+/// reading an assembly needs `Artifact:Get` on it, which group-wide grants give. a key limited
+/// to single plugins may read it instead if it's allowed every plugin in it, the same check
+/// `request_assembly` made when the assembly was requested.
+pub async fn require_assembly_access(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    assembly_id: Uuid,
+) -> Result<(), Error> {
+    let checker = PermissionChecker::new(&state.db, entity);
+    let check = PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id);
+
+    if checker
+        .can(check.clone().with_resource_id(assembly_id))
+        .await?
+    {
+        return Ok(());
+    }
+    if !checker.can_any(check.clone()).await? {
+        return Err(Error::Forbidden);
+    }
+
+    let plugin_ids =
+        database::assembly::get_assembly_plugin_ids(&state.db, group_id, assembly_id).await?;
+    if plugin_ids.is_empty() {
+        return Err(Error::Forbidden);
+    }
+    for plugin_id in plugin_ids {
+        let Some(plugin_id) = plugin_id else {
+            return Err(Error::Forbidden);
+        };
+        if !checker
+            .can(check.clone().with_resource_id(plugin_id))
+            .await?
+        {
+            return Err(Error::Forbidden);
+        }
+    }
+
+    Ok(())
+}
+
+fn response(
+    state: &AppState,
+    group_id: Uuid,
+    id: Uuid,
+    reused: bool,
+) -> Result<RequestAssemblyResponse, Error> {
     let path = format!("/api/v1/groups/{group_id}/assemblies/{id}");
     let status_uri = state
         .config
@@ -66,5 +298,9 @@ pub async fn request_assembly(
         .map_err(|_| Error::Internal("url join failed".into()))?
         .to_string();
 
-    Ok(RequestAssemblyResponse { id, status_uri })
+    Ok(RequestAssemblyResponse {
+        id,
+        status_uri,
+        reused,
+    })
 }
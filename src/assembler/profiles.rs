@@ -0,0 +1,156 @@
+use uuid::Uuid;
+
+use crate::{
+    assembler::{
+        self, ArtifactCoordinate, AssemblyOptions, RequestAssemblyResponse, errors::AssemblyError,
+        releases,
+    },
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::assembly::{AssemblyProfile, ProfileRun},
+    server::AppState,
+};
+
+async fn find_profile(
+    state: &AppState,
+    group_id: Uuid,
+    name: &str,
+) -> Result<AssemblyProfile, Error> {
+    database::profiles::get_profile(&state.db, group_id, name)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("profile {name} not found")))
+}
+
+/// saving over an existing profile keeps its history
+pub async fn save_profile(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+    artifacts: Vec<ArtifactCoordinate>,
+    options: AssemblyOptions,
+) -> Result<AssemblyProfile, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Create).in_group(group_id))
+        .await?;
+
+    // same rules as release names
+    releases::validate_name(name)
+        .map_err(|_| Error::BadRequest(format!("invalid profile name: {name}")))?;
+
+    if artifacts.is_empty() {
+        return Err(AssemblyError::NoArtifacts.into());
+    }
+
+    let profile = database::profiles::save_profile(
+        &state.db,
+        group_id,
+        name,
+        &artifacts,
+        &options,
+        entity.raw_uuid(),
+    )
+    .await?;
+
+    tracing::info!(group_id = %group_id, profile = %name, "assembly profile saved");
+
+    Ok(profile)
+}
+
+pub async fn get_profile(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<AssemblyProfile, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?;
+
+    find_profile(state, group_id, name).await
+}
+
+pub async fn list_profiles(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<AssemblyProfile>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::profiles::list_profiles(&state.db, group_id).await?)
+}
+
+/// assemblies it produced are left alone
+pub async fn delete_profile(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Delete).in_group(group_id))
+        .await?;
+
+    if !database::profiles::delete_profile(&state.db, group_id, name).await? {
+        return Err(Error::NotFound(format!("profile {name} not found")));
+    }
+
+    tracing::info!(group_id = %group_id, profile = %name, "assembly profile deleted");
+
+    Ok(())
+}
+
+/// requests an assembly exactly like `assembler::request_assembly` would, and records it in the
+/// profile's history
+pub async fn assemble_profile(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<RequestAssemblyResponse, Error> {
+    // per plugin grants are checked by `request_assembly`
+    if !PermissionChecker::new(&state.db, entity)
+        .can_any(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?
+    {
+        return Err(Error::Forbidden);
+    }
+
+    let profile = find_profile(state, group_id, name).await?;
+
+    let response =
+        assembler::request_assembly(state, entity, group_id, profile.artifacts, profile.options)
+            .await?;
+
+    database::profiles::record_run(
+        &state.db,
+        profile.id,
+        response.id,
+        entity.raw_uuid(),
+        response.reused,
+    )
+    .await?;
+
+    Ok(response)
+}
+
+pub async fn list_profile_runs(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<Vec<ProfileRun>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?;
+
+    let profile = find_profile(state, group_id, name).await?;
+
+    Ok(database::profiles::list_runs(&state.db, profile.id, 100).await?)
+}
@@ -0,0 +1,103 @@
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::assembly::{Assembly, AssemblyRelease},
+    server::AppState,
+};
+
+/// release names end up in download filenames
+pub fn validate_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!("invalid release name: {name}")))
+    }
+}
+
+/// a pinned assembly is exempt from expiry and keeps its blob, and with it its share of the
+/// group's quota, until it's unpinned
+pub async fn pin_release(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+    assembly_id: Uuid,
+) -> Result<Assembly, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(
+            PermissionCheck::new(ResourceType::Artifact, Action::Create)
+                .in_group(group_id)
+                .with_resource_id(assembly_id),
+        )
+        .await?;
+
+    validate_name(name)?;
+
+    let assembly = database::assembly::get_assembly(&state.db, group_id, assembly_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("assembly {assembly_id} not found")))?;
+
+    if assembly.status != "completed" {
+        return Err(Error::BadRequest(
+            "only completed assemblies can be pinned".into(),
+        ));
+    }
+
+    if !database::assembly::pin_release(&state.db, group_id, assembly_id, name).await? {
+        return Err(match assembly.release {
+            Some(existing) => Error::Conflict(format!("assembly is already pinned as {existing}")),
+            None => Error::Conflict(format!("release {name} already exists")),
+        });
+    }
+
+    tracing::info!(group_id = %group_id, assembly_id = %assembly_id, name, "release pinned");
+
+    Ok(Assembly {
+        release: Some(name.to_string()),
+        ..assembly
+    })
+}
+
+pub async fn unpin_release(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Delete).in_group(group_id))
+        .await?;
+
+    let assembly_id = database::assembly::unpin_release(&state.db, group_id, name)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("release {name} not found")))?;
+
+    tracing::info!(group_id = %group_id, assembly_id = %assembly_id, name, "release unpinned");
+
+    Ok(())
+}
+
+pub async fn list_releases(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<AssemblyRelease>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::assembly::list_releases(&state.db, group_id).await?)
+}
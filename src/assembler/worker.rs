@@ -1,95 +1,309 @@
 use std::{
-    io::Write,
+    collections::{BTreeMap, BTreeSet},
+    io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write},
     sync::{
         Arc,
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
 };
 
 use bytes::Bytes;
 use chrono::Utc;
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
-use std::time::Duration;
-use tokio::sync::{RwLock, mpsc};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, RwLock};
+use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    assembler::{errors::AssemblyError, job::AssemblyJob},
+    assembler::{
+        ArtifactCoordinate,
+        archive::{
+            ArchiveFormat, ArchiveWriter, EMBEDDED_MANIFEST_PATH, EmbeddedArtifact,
+            EmbeddedManifest, ManifestEntry,
+        },
+        compat::check_java_version,
+        conflicts::check_conflicts,
+        errors::AssemblyError,
+        events::AssemblyWatchers,
+        job::AssemblyJob,
+        layout::jar_path,
+    },
+    configs::{referenced_secrets, render},
     database::{
-        assembly::{get_artifact, set_assembly_status},
-        blobs::create_blob,
+        assembly::{
+            claim_next_assembly, fail_exhausted_assemblies, finish_assembly, get_artifact,
+            load_assembly_job, renew_assembly_lease, set_artifact_checksum, set_assembly_progress,
+        },
+        blobs::{add_blob_ref, create_blob, find_by_sha256, remove_blob_ref},
+        configs::get_assembly_configs,
+        secrets::get_encrypted_secret,
     },
     models::{
-        assembly::AssemblyStatus, blobs::BlobEntityType, plugins::PluginSource,
+        assembly::{ArtifactTiming, AssemblyProgress, AssemblyStatus, ProgressStage},
+        blobs::BlobEntityType,
+        configs::ConfigTemplateVersion,
+        plugins::PluginSource,
         settings::InstanceSettings,
     },
-    storage::{LocalStorage, StorageError, filesystem::FilesystemStorage},
+    plugins::{bytecode::min_java_version, descriptor::read_descriptor},
+    secrets::{SecretError, SecretsKey},
+    storage::{
+        StorageError,
+        filesystem::{FilesystemStorage, TempFile},
+    },
+    webhooks,
 };
 
-pub async fn run(
-    mut rx: mpsc::Receiver<AssemblyJob>,
-    pool: SqlitePool,
-    settings: Arc<RwLock<InstanceSettings>>,
-    storage: FilesystemStorage,
-    active_jobs: Arc<AtomicUsize>,
-) {
-    while let Some(job) = rx.recv().await {
-        let pool = pool.clone();
-        let settings = settings.clone();
-        let storage = storage.clone();
-        let active = active_jobs.clone();
-
-        tokio::spawn(async move {
-            active.fetch_add(1, Ordering::Relaxed);
-            tracing::info!(
-                "assembly job started, active workers: {}",
-                active.load(Ordering::Relaxed)
-            );
+/// how often the queue is polled when nobody notifies, this also picks up expired leases
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
 
-            let (timeout_secs, expires_secs) = {
-                let s = settings.read().await;
-                (s.assembly_timeout_secs, s.assembly_expiry_secs)
-            };
+#[derive(Clone)]
+pub struct WorkerContext {
+    pub pool: SqlitePool,
+    pub settings: Arc<RwLock<InstanceSettings>>,
+    pub storage: FilesystemStorage,
+    pub active_jobs: Arc<AtomicUsize>,
+    pub watchers: AssemblyWatchers,
+    pub secrets_key: Option<SecretsKey>,
+    pub public_base_url: Url,
+    /// overrides the `assembly_max_workers` instance setting for this process
+    pub max_workers: Option<usize>,
+}
 
-            let result = tokio::time::timeout(
-                Duration::from_secs(timeout_secs),
-                process(job.clone(), &pool, &storage),
+/// the `assemblies` table is the queue. `notify` only wakes the loop early, every pending
+/// or abandoned assembly is found by polling the table, including ones from before a restart
+/// and ones requested through another process.
+pub async fn run(ctx: WorkerContext, notify: Arc<Notify>) {
+    loop {
+        let (lease_secs, max_attempts, max_workers, max_per_group) = {
+            let s = ctx.settings.read().await;
+            (
+                s.assembly_lease_secs,
+                s.assembly_max_attempts,
+                ctx.max_workers.unwrap_or(s.assembly_max_workers),
+                s.assembly_max_per_group,
             )
-            .await;
-
-            let status = match result {
-                Ok(Ok(blob_id)) => {
-                    tracing::info!(assembly_id = %job.id, "assembly completed");
-                    let expires_at = Utc::now() + chrono::Duration::seconds(expires_secs as i64);
+        };
 
-                    AssemblyStatus::Completed {
-                        blob_id,
-                        expires_at,
-                    }
+        match fail_exhausted_assemblies(&ctx.pool, max_attempts).await {
+            Ok(exhausted) => {
+                if !exhausted.is_empty() {
+                    tracing::warn!(
+                        count = exhausted.len(),
+                        "gave up on repeatedly abandoned assemblies"
+                    );
                 }
-                Ok(Err(e)) => {
-                    tracing::error!(assembly_id = %job.id, error = %e, "assembly failed");
-                    AssemblyStatus::Failed { error: e }
+                for (group_id, id) in exhausted {
+                    ctx.watchers.notify(id);
+                    notify_webhooks(&ctx.pool, group_id, id).await;
                 }
-                Err(_) => {
-                    tracing::error!(assembly_id = %job.id, timeout_secs, "assembly timed out");
-                    AssemblyStatus::Failed {
-                        error: AssemblyError::Timeout(timeout_secs),
-                    }
+            }
+            Err(e) => tracing::error!(error = %e, "failed to fail exhausted assemblies"),
+        }
+
+        // finishing jobs notify, so a full pool just waits here for a free slot
+        while ctx.active_jobs.load(Ordering::Relaxed) < max_workers {
+            let lease_id = Uuid::now_v7();
+            let lease_expires_at = Utc::now() + chrono::Duration::seconds(lease_secs as i64);
+
+            match claim_next_assembly(
+                &ctx.pool,
+                lease_id,
+                lease_expires_at,
+                max_attempts,
+                max_per_group,
+            )
+            .await
+            {
+                Ok(Some(id)) => {
+                    ctx.active_jobs.fetch_add(1, Ordering::Relaxed);
+                    tokio::spawn(execute(ctx.clone(), notify.clone(), id, lease_id));
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to claim assembly");
+                    break;
                 }
+            }
+        }
+
+        tokio::select! {
+            _ = notify.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// expects the caller to have counted the job in `active_jobs` already
+async fn execute(ctx: WorkerContext, notify: Arc<Notify>, id: Uuid, lease_id: Uuid) {
+    let active = &ctx.active_jobs;
+    tracing::info!(
+        assembly_id = %id,
+        "assembly job started, active workers: {}",
+        active.load(Ordering::Relaxed)
+    );
+
+    let (timeout_secs, expires_secs, lease_secs) = {
+        let s = ctx.settings.read().await;
+        (
+            s.assembly_timeout_secs,
+            s.assembly_expiry_secs,
+            s.assembly_lease_secs,
+        )
+    };
+
+    let mut group_id = None;
+    let result = match load_assembly_job(&ctx.pool, id).await {
+        Ok(Some(job)) => {
+            group_id = Some(job.group_id);
+            let mut progress = Progress {
+                pool: &ctx.pool,
+                watchers: &ctx.watchers,
+                id,
+                lease_id,
+                current: AssemblyProgress {
+                    stage: ProgressStage::Resolving,
+                    artifacts: Vec::with_capacity(job.artifacts.len()),
+                },
             };
+            let build = tokio::time::timeout(
+                Duration::from_secs(timeout_secs),
+                process(
+                    job,
+                    &ctx.pool,
+                    &ctx.storage,
+                    ctx.secrets_key.as_ref(),
+                    &ctx.public_base_url,
+                    &mut progress,
+                ),
+            );
 
-            if let Err(e) = set_assembly_status(&pool, job.id, status).await {
-                tracing::error!(assembly_id = %job.id, error = %e, "failed to update assembly status");
+            tokio::select! {
+                result = build => result,
+                _ = keep_lease(&ctx.pool, id, lease_id, lease_secs) => {
+                    // dropping the build flags its blocking half, which stops at the next
+                    // entry and removes its temp file
+                    tracing::warn!(assembly_id = %id, "assembly cancelled or lease lost, abandoning build");
+                    active.fetch_sub(1, Ordering::Relaxed);
+                    notify.notify_one();
+                    return;
+                }
             }
+        }
+        Ok(None) => Ok(Err(AssemblyError::Internal("assembly disappeared".into()))),
+        Err(e) => Ok(Err(e.into())),
+    };
 
-            active.fetch_sub(1, Ordering::Relaxed);
-            tracing::info!(
-                assembly_id = %job.id,
-                active = active.load(Ordering::Relaxed),
-                "assembly job finished"
-            );
-        });
+    let status = match result {
+        Ok(Ok(blob_id)) => {
+            tracing::info!(assembly_id = %id, "assembly completed");
+            let expires_at = Utc::now() + chrono::Duration::seconds(expires_secs as i64);
+
+            AssemblyStatus::Completed {
+                blob_id,
+                expires_at,
+            }
+        }
+        Ok(Err(e)) => {
+            tracing::error!(assembly_id = %id, error = %e, "assembly failed");
+            AssemblyStatus::Failed { error: e }
+        }
+        Err(_) => {
+            tracing::error!(assembly_id = %id, timeout_secs, "assembly timed out");
+            AssemblyStatus::Failed {
+                error: AssemblyError::Timeout(timeout_secs),
+            }
+        }
+    };
+
+    let completed_blob = match &status {
+        AssemblyStatus::Completed { blob_id, .. } => Some(*blob_id),
+        _ => None,
+    };
+
+    let finished = finish_assembly(&ctx.pool, id, lease_id, status).await;
+    ctx.watchers.notify(id);
+    match finished {
+        Ok(true) => {
+            if let Some(group_id) = group_id {
+                notify_webhooks(&ctx.pool, group_id, id).await;
+            }
+        }
+        Ok(false) => {
+            tracing::warn!(assembly_id = %id, "assembly cancelled or lease lost before finishing");
+            // nobody will ever download this archive
+            if let (Some(blob_id), Some(group_id)) = (completed_blob, group_id) {
+                let entity = BlobEntityType::Assembly { id };
+                if let Err(e) =
+                    remove_blob_ref(&ctx.pool, &ctx.storage, blob_id, group_id, entity).await
+                {
+                    tracing::error!(assembly_id = %id, error = %e, "failed to remove orphaned assembly blob");
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!(assembly_id = %id, error = %e, "failed to update assembly status")
+        }
+    }
+
+    active.fetch_sub(1, Ordering::Relaxed);
+    notify.notify_one();
+    tracing::info!(
+        assembly_id = %id,
+        active = active.load(Ordering::Relaxed),
+        "assembly job finished"
+    );
+}
+
+async fn notify_webhooks(pool: &SqlitePool, group_id: Uuid, id: Uuid) {
+    if let Err(e) = webhooks::assembly_finished(pool, group_id, id).await {
+        tracing::error!(assembly_id = %id, error = %e, "failed to queue assembly webhooks");
+    }
+}
+
+/// renews the lease at a third of its length, returns once it's lost
+async fn keep_lease(pool: &SqlitePool, id: Uuid, lease_id: Uuid, lease_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs((lease_secs / 3).max(1)));
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let lease_expires_at = Utc::now() + chrono::Duration::seconds(lease_secs as i64);
+
+        match renew_assembly_lease(pool, id, lease_id, lease_expires_at).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            // a database hiccup isn't a lost lease, try again next tick
+            Err(e) => {
+                tracing::warn!(assembly_id = %id, error = %e, "failed to renew assembly lease")
+            }
+        }
+    }
+}
+
+/// the worker side of `AssemblyProgress`. it's stored on the assembly rather than kept in
+/// memory, so status polls and event streams can follow a build running in any process.
+struct Progress<'a> {
+    pool: &'a SqlitePool,
+    watchers: &'a AssemblyWatchers,
+    id: Uuid,
+    lease_id: Uuid,
+    current: AssemblyProgress,
+}
+
+impl Progress<'_> {
+    async fn stage(&mut self, stage: ProgressStage) {
+        self.current.stage = stage;
+
+        // best effort, a missed update must not fail the build
+        if let Err(e) =
+            set_assembly_progress(self.pool, self.id, self.lease_id, &self.current).await
+        {
+            tracing::warn!(assembly_id = %self.id, error = %e, "failed to record assembly progress");
+        }
+        self.watchers.notify(self.id);
     }
 }
 
@@ -97,67 +311,407 @@ async fn process(
     job: AssemblyJob,
     pool: &SqlitePool,
     storage: &FilesystemStorage,
+    secrets_key: Option<&SecretsKey>,
+    public_base_url: &Url,
+    progress: &mut Progress<'_>,
 ) -> Result<Uuid, AssemblyError> {
-    set_assembly_status(pool, job.id, AssemblyStatus::Running).await?;
+    progress.stage(ProgressStage::Resolving).await;
+
+    let configs = get_assembly_configs(pool, job.id).await?;
+    let secrets = resolve_secrets(pool, job.group_id, secrets_key, &configs).await?;
+    let rendered = configs
+        .into_iter()
+        .map(
+            |config| match render(&config.content, &job.options.variables, &secrets) {
+                Ok(content) => Ok((config.path, content)),
+                Err(e) => Err(AssemblyError::InvalidConfig(config.path, e.to_string())),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let mut zip_buf = Vec::new();
-    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut zip_buf));
-    let options = zip::write::FileOptions::<()>::default()
-        .compression_method(zip::CompressionMethod::Deflated);
+    let total = job.artifacts.len();
+    let mut jars = Vec::with_capacity(total);
+    for (i, coord) in job.artifacts.iter().enumerate() {
+        progress
+            .stage(ProgressStage::Fetching {
+                artifact: i + 1,
+                total,
+            })
+            .await;
+        let started = Instant::now();
 
-    for coord in &job.artifacts {
         let resolved = get_artifact(pool, coord, job.group_id).await?;
 
-        let data = if resolved.version.is_cached() {
-            let blob_id = resolved.version.blob_id.unwrap();
-            storage.get(blob_id).await.map_err(|e| match e {
-                StorageError::NotFound(_) => AssemblyError::ArtifactNotFound(blob_id.to_string()),
-                e => AssemblyError::Storage(e),
-            })?
-        } else {
-            fetch_external(&resolved.plugin.source, &coord.version).await?
+        let source = match resolved.version.blob_id {
+            Some(blob_id) => JarSource::Blob(blob_id),
+            // checked on request too, but the cached jar may have been dropped since
+            None if job.options.format == ArchiveFormat::Manifest => {
+                return Err(AssemblyError::InvalidPlugin(
+                    coord.to_string(),
+                    "not cached, so a manifest can't point at it".into(),
+                ));
+            }
+            None => {
+                JarSource::Fetched(fetch_external(&resolved.plugin.source, &coord.version).await?)
+            }
         };
 
-        let filename = format!(
-            "{}.{}-{}.jar",
-            coord.group_id, coord.artifact_id, coord.version
-        );
-        zip.start_file(filename, options)?;
-        zip.write_all(&data)?;
+        jars.push(PlannedJar {
+            index: i,
+            coord: coord.clone(),
+            path: jar_path(job.options.layout, job.options.filename.as_deref(), coord)?,
+            origin: resolved.plugin.source.to_string(),
+            source,
+        });
+        progress.current.artifacts.push(ArtifactTiming {
+            artifact: coord.to_string(),
+            resolve_ms: started.elapsed().as_millis() as u64,
+            write_ms: None,
+        });
     }
 
-    zip.finish()?;
+    progress.stage(ProgressStage::Compressing).await;
 
-    let zip_bytes = Bytes::from(zip_buf);
-    let sha256 = {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(&zip_bytes);
-        format!("{:x}", hasher.finalize())
+    let mut temp = storage.temp_file().await?;
+    let abandoned = Arc::new(AtomicBool::new(false));
+    let _abandon = AbandonOnDrop(abandoned.clone());
+    let (temp, sha256, size_bytes, built) = {
+        let job = job.clone();
+        let storage = storage.clone();
+        let public_base_url = public_base_url.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let (sha256, size_bytes, built) = build(
+                &job,
+                jars,
+                rendered,
+                &storage,
+                &public_base_url,
+                &mut temp,
+                &abandoned,
+            )?;
+            Ok::<_, AssemblyError>((temp, sha256, size_bytes, built))
+        })
+        .await
+        .map_err(|e| AssemblyError::Internal(e.to_string()))??
     };
 
-    let blob_id = Uuid::now_v7();
-    let size_bytes = zip_bytes.len() as i64;
+    for (timing, jar) in progress.current.artifacts.iter_mut().zip(&built) {
+        timing.write_ms = Some(jar.write_ms);
+    }
+    progress.stage(ProgressStage::Storing).await;
 
-    let mut tx = pool.begin().await?;
+    let entity = BlobEntityType::Assembly { id: job.id };
+    // immediate, so a concurrent build of the same bytes waits instead of racing the lookup
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
 
-    create_blob(
-        &mut *tx,
-        job.group_id,
-        blob_id,
-        BlobEntityType::Assembly { id: job.id },
-        sha256,
-        size_bytes,
-    )
-    .await?;
+    // archives carry their assembly id, but manifest outputs of identical builds are identical
+    let blob_id = match find_by_sha256(&mut *tx, sha256.clone()).await? {
+        Some(existing) => {
+            add_blob_ref(&mut tx, existing.id, job.group_id, entity).await?;
+            existing.id
+        }
+        None => {
+            let blob_id = Uuid::now_v7();
+            create_blob(
+                &mut tx,
+                job.group_id,
+                blob_id,
+                entity,
+                sha256,
+                size_bytes as i64,
+            )
+            .await?;
+            storage.persist(temp, blob_id).await?;
+            blob_id
+        }
+    };
 
-    storage.put(blob_id, zip_bytes).await?;
+    for (coord, jar) in job.artifacts.iter().zip(&built) {
+        set_artifact_checksum(&mut tx, job.id, coord, &jar.sha256, jar.size_bytes).await?;
+    }
 
     tx.commit().await?;
 
     Ok(blob_id)
 }
 
+/// the blocking build outlives a dropped future, so this tells it to stop when the build is
+/// cancelled, loses its lease or times out
+struct AbandonOnDrop(Arc<AtomicBool>);
+
+impl Drop for AbandonOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+enum JarSource {
+    Blob(Uuid),
+    Fetched(Bytes),
+}
+
+struct PlannedJar {
+    /// position in the request, entries are written in path order instead
+    index: usize,
+    coord: ArtifactCoordinate,
+    path: String,
+    /// the plugin's source, for the embedded manifest
+    origin: String,
+    source: JarSource,
+}
+
+/// what went into the archive for one jar
+struct BuiltJar {
+    write_ms: u64,
+    sha256: String,
+    size_bytes: u64,
+}
+
+/// hashes everything read through it, so a jar is checksummed on its way into the archive
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+enum Output<W: Write + Seek> {
+    Archive(ArchiveWriter<W>),
+    Manifest(Vec<ManifestEntry>),
+}
+
+/// streams every jar from storage into the temp file one at a time, so memory stays bounded
+/// by the copy buffers no matter how big the assembly is. returns the sha256 and size of the
+/// archive and what was written for each jar, in request order.
+///
+/// jars, then configs, then the embedded manifest, each sorted by path, so the same inputs
+/// always give the same archive. `abandoned` is checked between entries.
+fn build(
+    job: &AssemblyJob,
+    mut jars: Vec<PlannedJar>,
+    mut configs: Vec<(String, String)>,
+    storage: &FilesystemStorage,
+    public_base_url: &Url,
+    temp: &mut TempFile,
+    abandoned: &AtomicBool,
+) -> Result<(String, u64, Vec<BuiltJar>), AssemblyError> {
+    let check_abandoned = || match abandoned.load(Ordering::Relaxed) {
+        true => Err(AssemblyError::Abandoned),
+        false => Ok(()),
+    };
+
+    let out = BufWriter::new(temp.file.try_clone()?);
+    let mut output = match job.options.format {
+        ArchiveFormat::Manifest => Output::Manifest(Vec::with_capacity(jars.len())),
+        format => Output::Archive(ArchiveWriter::new(format, out)?),
+    };
+
+    let mut descriptors = Vec::new();
+    let mut java_targets = Vec::new();
+    let mut built = Vec::with_capacity(jars.len());
+    let mut embedded = Vec::with_capacity(jars.len());
+
+    jars.sort_by(|a, b| a.path.cmp(&b.path));
+    configs.sort();
+
+    for jar in jars {
+        check_abandoned()?;
+        let started = Instant::now();
+        let jar_index = jar.index;
+        let coord = &jar.coord;
+        let invalid = |e: &dyn std::fmt::Display| {
+            AssemblyError::InvalidPlugin(coord.to_string(), e.to_string())
+        };
+
+        let mut data: Box<dyn ReadSeek> = match jar.source {
+            JarSource::Blob(blob_id) => {
+                Box::new(storage.open_blocking(blob_id).map_err(|e| match e {
+                    StorageError::NotFound(_) => {
+                        AssemblyError::ArtifactNotFound(blob_id.to_string())
+                    }
+                    e => AssemblyError::Storage(e),
+                })?)
+            }
+            JarSource::Fetched(bytes) => Box::new(Cursor::new(bytes)),
+        };
+
+        if let Some(descriptor) = read_descriptor(&mut data).map_err(|e| invalid(&e))? {
+            descriptors.push((coord.clone(), descriptor));
+        }
+
+        // rescanned here since jars uploaded before detection existed have no stored target
+        if job.options.java_version.is_some() {
+            data.rewind()?;
+            let required = min_java_version(&mut data).map_err(|e| invalid(&e))?;
+            java_targets.push((coord.clone(), required));
+        }
+
+        let size = data.seek(SeekFrom::End(0))?;
+        data.rewind()?;
+
+        let sha256 = match &mut output {
+            Output::Archive(archive) => {
+                let mut hashing = HashingReader {
+                    inner: &mut data,
+                    hasher: Sha256::new(),
+                };
+                archive.add(&jar.path, &mut hashing, size)?;
+                let sha256 = format!("{:x}", hashing.hasher.finalize());
+
+                embedded.push(EmbeddedArtifact {
+                    coordinate: coord.to_string(),
+                    path: jar.path,
+                    source: jar.origin,
+                    sha256: sha256.clone(),
+                });
+                sha256
+            }
+            Output::Manifest(entries) => {
+                let (sha256, size_bytes) = hash_reader(&mut data)?;
+                entries.push(ManifestEntry {
+                    group_id: coord.group_id.clone(),
+                    artifact_id: coord.artifact_id.clone(),
+                    version: coord.version.clone(),
+                    path: jar.path,
+                    sha256: sha256.clone(),
+                    size_bytes,
+                    url: plugin_download_url(public_base_url, job.group_id, coord)?,
+                });
+                sha256
+            }
+        };
+
+        built.push((
+            jar_index,
+            BuiltJar {
+                write_ms: started.elapsed().as_millis() as u64,
+                sha256,
+                size_bytes: size,
+            },
+        ));
+    }
+
+    check_conflicts(&descriptors)?;
+    if let Some(target) = job.options.java_version {
+        check_java_version(target, &java_targets)?;
+    }
+
+    let mut out = match output {
+        Output::Archive(mut archive) => {
+            for (path, content) in configs {
+                check_abandoned()?;
+                archive.add(&path, content.as_bytes(), content.len() as u64)?;
+            }
+
+            let manifest = serde_json::to_vec_pretty(&EmbeddedManifest {
+                assembly_id: job.id,
+                group_id: job.group_id,
+                requested_at: requested_at(job.id),
+                cogere_version: crate::VERSION,
+                cogere_git_sha: crate::GIT_SHA,
+                artifacts: embedded,
+            })
+            .map_err(|e| AssemblyError::Internal(e.to_string()))?;
+            archive.add(
+                EMBEDDED_MANIFEST_PATH,
+                manifest.as_slice(),
+                manifest.len() as u64,
+            )?;
+
+            archive.finish()?
+        }
+        Output::Manifest(entries) => {
+            let mut out = BufWriter::new(temp.file.try_clone()?);
+            serde_json::to_writer_pretty(&mut out, &entries)
+                .map_err(|e| AssemblyError::Internal(e.to_string()))?;
+            out
+        }
+    };
+    out.flush()?;
+    drop(out);
+
+    temp.file.rewind()?;
+    let (sha256, size) = hash_reader(&mut temp.file)?;
+    built.sort_by_key(|(index, _)| *index);
+    Ok((
+        sha256,
+        size,
+        built.into_iter().map(|(_, jar)| jar).collect(),
+    ))
+}
+
+/// assembly ids are uuid v7, so they carry the time they were requested at
+fn requested_at(id: Uuid) -> chrono::DateTime<Utc> {
+    id.get_timestamp()
+        .and_then(|ts| {
+            let (secs, nanos) = ts.to_unix();
+            chrono::DateTime::from_timestamp(secs as i64, nanos)
+        })
+        .unwrap_or_default()
+}
+
+/// sha256 and length of everything left in `reader`, read in chunks
+pub(crate) fn hash_reader<R: Read>(mut reader: R) -> std::io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut reader, &mut hasher)?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+fn plugin_download_url(
+    base: &Url,
+    group_id: Uuid,
+    coord: &ArtifactCoordinate,
+) -> Result<String, AssemblyError> {
+    let path = format!(
+        "/api/v1/groups/{group_id}/plugins/{}/{}/{}/download",
+        coord.group_id, coord.artifact_id, coord.version
+    );
+    base.join(&path)
+        .map(|url| url.to_string())
+        .map_err(|_| AssemblyError::Internal("url join failed".into()))
+}
+
+/// decrypts only the secrets the pinned configs reference. the values never leave this
+/// map and the rendered zip, so nothing here may be logged.
+async fn resolve_secrets(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    key: Option<&SecretsKey>,
+    configs: &[ConfigTemplateVersion],
+) -> Result<BTreeMap<String, String>, AssemblyError> {
+    let names: BTreeSet<String> = configs
+        .iter()
+        .flat_map(|config| referenced_secrets(&config.content))
+        .collect();
+
+    if names.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let key = key.ok_or(SecretError::NotConfigured)?;
+
+    let mut secrets = BTreeMap::new();
+    for name in names {
+        let encrypted = get_encrypted_secret(pool, group_id, &name)
+            .await?
+            .ok_or_else(|| SecretError::NotFound(name.clone()))?;
+        let value = key.decrypt(group_id, &name, &encrypted)?;
+        secrets.insert(name, value);
+    }
+
+    Ok(secrets)
+}
+
 async fn fetch_external(source: &PluginSource, _version: &str) -> Result<Bytes, AssemblyError> {
     match source {
         PluginSource::External {
@@ -169,3 +723,196 @@ async fn fetch_external(source: &PluginSource, _version: &str) -> Result<Bytes,
         PluginSource::Local => unreachable!("local plugins must be cached"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Read, Write},
+        path::PathBuf,
+    };
+
+    use bytes::Bytes;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::{
+        assembler::{AssemblyOptions, layout::Layout},
+        database::{
+            assembly::create_assembly,
+            plugins::{CreateLocalPluginOptions, create_local_plugin},
+        },
+        storage::LocalStorage,
+    };
+
+    struct Env {
+        root: PathBuf,
+        pool: SqlitePool,
+        storage: FilesystemStorage,
+        group_id: Uuid,
+        coord: ArtifactCoordinate,
+    }
+
+    impl Drop for Env {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn jar() -> Bytes {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in [
+            (
+                "plugin.yml",
+                &b"name: Example\nversion: 1.0.0\nmain: a.A\n"[..],
+            ),
+            ("a/A.class", &[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 61][..]),
+        ] {
+            writer
+                .start_file(name, zip::write::FileOptions::<()>::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        Bytes::from(writer.finish().unwrap().into_inner())
+    }
+
+    /// a fresh database and storage with one cached plugin in one group
+    async fn env() -> Env {
+        let root = std::env::temp_dir().join(format!("cogere-worker-{}", Uuid::now_v7()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let pool = SqlitePoolOptions::new()
+            .connect(&format!("sqlite://{}/db.sqlite?mode=rwc", root.display()))
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        let group_id = Uuid::now_v7();
+        sqlx::query("INSERT INTO groups (id, name) VALUES (?, 'test')")
+            .bind(group_id.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let storage = FilesystemStorage::new(root.join("blobs"));
+        std::fs::create_dir_all(root.join("blobs")).unwrap();
+
+        let jar = jar();
+        let blob_id = Uuid::now_v7();
+        storage.put(blob_id, jar.clone()).await.unwrap();
+        let (sha256, size_bytes) = hash_reader(Cursor::new(&jar)).unwrap();
+
+        let coord = ArtifactCoordinate {
+            group_id: "com.example".into(),
+            artifact_id: "example".into(),
+            version: "1.0.0".into(),
+        };
+        create_local_plugin(
+            &pool,
+            CreateLocalPluginOptions {
+                plugin_id: Uuid::now_v7(),
+                version_id: Uuid::now_v7(),
+                blob_id,
+                group_id,
+                plugin_group_id: coord.group_id.clone(),
+                plugin_artifact_id: coord.artifact_id.clone(),
+                version: coord.version.clone(),
+                sha256,
+                size_bytes,
+                is_new_blob: true,
+                api_version: None,
+                game_versions: Vec::new(),
+                min_java_version: Some(17),
+            },
+        )
+        .await
+        .unwrap();
+
+        Env {
+            root,
+            pool,
+            storage,
+            group_id,
+            coord,
+        }
+    }
+
+    async fn assemble(env: &Env, format: ArchiveFormat) -> (Uuid, Vec<u8>) {
+        let options = AssemblyOptions {
+            layout: Layout::Paper,
+            format,
+            ..Default::default()
+        };
+        let id = create_assembly(
+            &env.pool,
+            env.group_id,
+            vec![env.coord.clone()],
+            &options,
+            &[],
+            "test",
+        )
+        .await
+        .unwrap();
+
+        let job = load_assembly_job(&env.pool, id).await.unwrap().unwrap();
+        let watchers = AssemblyWatchers::default();
+        let mut progress = Progress {
+            pool: &env.pool,
+            watchers: &watchers,
+            id,
+            lease_id: Uuid::now_v7(),
+            current: AssemblyProgress {
+                stage: ProgressStage::Resolving,
+                artifacts: Vec::new(),
+            },
+        };
+        let base = Url::parse("https://cogere.example/").unwrap();
+        let blob_id = process(job, &env.pool, &env.storage, None, &base, &mut progress)
+            .await
+            .unwrap();
+
+        let mut out = Vec::new();
+        env.storage
+            .open_blocking(blob_id)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        (id, out)
+    }
+
+    #[tokio::test]
+    async fn builds_a_zip() {
+        let env = env().await;
+        let (id, out) = assemble(&env, ArchiveFormat::Zip).await;
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(out)).unwrap();
+        let path = jar_path(Layout::Paper, None, &env.coord).unwrap();
+        assert_eq!(archive.by_name(&path).unwrap().size(), jar().len() as u64);
+
+        let mut manifest = String::new();
+        archive
+            .by_name(EMBEDDED_MANIFEST_PATH)
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest["assembly_id"], id.to_string());
+        assert_eq!(manifest["artifacts"][0]["path"], path);
+    }
+
+    #[tokio::test]
+    async fn builds_a_manifest() {
+        let env = env().await;
+        let (_, out) = assemble(&env, ArchiveFormat::Manifest).await;
+
+        let entries: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let (sha256, _) = hash_reader(Cursor::new(jar())).unwrap();
+        assert_eq!(entries[0]["sha256"], sha256);
+        assert_eq!(
+            entries[0]["url"],
+            format!(
+                "https://cogere.example/api/v1/groups/{}/plugins/com.example/example/1.0.0/download",
+                env.group_id
+            )
+        );
+    }
+}
@@ -5,7 +5,8 @@ use crate::auth::extractor::AuthenticatedEntity;
 use crate::auth::permissions::{Action, GroupRole, InstanceRole, PermissionCheck, ResourceType};
 use crate::database::groups::get_membership_by_user_and_group_id;
 use crate::database::machine_keys::{
-    machine_key_has_specific_permission, machine_key_has_wide_permission,
+    machine_key_has_any_permission, machine_key_has_specific_permission,
+    machine_key_has_wide_permission,
 };
 use crate::errors::Error;
 
@@ -42,6 +43,29 @@ impl<'a> PermissionChecker<'a> {
         }
     }
 
+    /// allowed on at least one resource of the type, for checks that narrow down per resource
+    /// afterwards. the resource id of the check is ignored.
+    pub async fn can_any(&self, check: PermissionCheck) -> Result<bool, Error> {
+        match self.entity {
+            AuthenticatedEntity::User(_) => self.can(check).await,
+            AuthenticatedEntity::Machine(key) => {
+                if let Some(group_id) = check.group_id
+                    && key.group_id != group_id
+                {
+                    return Ok(false);
+                }
+
+                Ok(machine_key_has_any_permission(
+                    self.db,
+                    key.id,
+                    check.resource_type.clone(),
+                    check.action.clone(),
+                )
+                .await?)
+            }
+        }
+    }
+
     async fn check_user(&self, user_id: Uuid, check: &PermissionCheck) -> Result<bool, Error> {
         let Some(group_id) = check.group_id else {
             return Ok(matches!(
@@ -107,7 +131,10 @@ impl<'a> PermissionChecker<'a> {
                 ResourceType::Group
                 | ResourceType::Plugin
                 | ResourceType::Artifact
-                | ResourceType::MachineKey => *role >= GroupRole::Admin,
+                | ResourceType::MachineKey
+                | ResourceType::Config
+                | ResourceType::Secret
+                | ResourceType::Webhook => *role >= GroupRole::Admin,
                 ResourceType::User => *role >= GroupRole::Owner,
             },
         }
@@ -58,6 +58,9 @@ pub enum ResourceType {
     Group,
     MachineKey,
     User,
+    Config,
+    Secret,
+    Webhook,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -72,6 +75,9 @@ impl fmt::Display for ResourceType {
             ResourceType::Group => write!(f, "group"),
             ResourceType::MachineKey => write!(f, "machine_key"),
             ResourceType::User => write!(f, "user"),
+            ResourceType::Config => write!(f, "config"),
+            ResourceType::Secret => write!(f, "secret"),
+            ResourceType::Webhook => write!(f, "webhook"),
         }
     }
 }
@@ -85,6 +91,9 @@ impl std::str::FromStr for ResourceType {
             "group" => Ok(Self::Group),
             "machine_key" => Ok(Self::MachineKey),
             "user" => Ok(Self::User),
+            "config" => Ok(Self::Config),
+            "secret" => Ok(Self::Secret),
+            "webhook" => Ok(Self::Webhook),
             other => Err(ResourceTypeParseError(other.to_string())),
         }
     }
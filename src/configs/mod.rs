@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::configs::ConfigTemplateVersion,
+    server::AppState,
+};
+
+/// placeholders starting with this are looked up in the group's secret store
+const SECRET_PREFIX: &str = "secret.";
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("unknown variable '{0}'")]
+    UnknownVariable(String),
+    #[error("unknown secret '{0}'")]
+    UnknownSecret(String),
+    #[error("unterminated placeholder")]
+    Unterminated,
+}
+
+/// config paths end up as zip entries, so they must stay relative and inside the archive
+pub fn validate_path(path: &str) -> Result<(), Error> {
+    let valid = !path.is_empty()
+        && !path.starts_with('/')
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..");
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!("invalid config path: {path}")))
+    }
+}
+
+pub async fn save_config(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    path: &str,
+    content: &str,
+) -> Result<ConfigTemplateVersion, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Config, Action::Create).in_group(group_id))
+        .await?;
+
+    validate_path(path)?;
+
+    let version =
+        database::configs::create_config_version(&state.db, group_id, path, content).await?;
+
+    tracing::info!(
+        group_id = %group_id,
+        path,
+        revision = version.revision,
+        "config template saved"
+    );
+
+    Ok(version)
+}
+
+/// substitutes `{{ name }}` placeholders from the request variables and `{{ secret.name }}`
+/// from decrypted group secrets. every placeholder must have a value so a typo can't
+/// silently ship an empty setting.
+pub fn render(
+    template: &str,
+    variables: &BTreeMap<String, String>,
+    secrets: &BTreeMap<String, String>,
+) -> Result<String, RenderError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or(RenderError::Unterminated)?;
+
+        let name = after[..end].trim();
+        let value = match name.strip_prefix(SECRET_PREFIX) {
+            Some(secret) => secrets
+                .get(secret)
+                .ok_or_else(|| RenderError::UnknownSecret(secret.to_string()))?,
+            None => variables
+                .get(name)
+                .ok_or_else(|| RenderError::UnknownVariable(name.to_string()))?,
+        };
+        out.push_str(value);
+
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// the secret names a template references, so the worker only decrypts what it needs
+pub fn referenced_secrets(template: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+
+        if let Some(secret) = after[..end].trim().strip_prefix(SECRET_PREFIX) {
+            names.insert(secret.to_string());
+        }
+
+        rest = &after[end + 2..];
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_variables_and_secrets() {
+        let rendered = render(
+            "motd: {{ motd }}\npassword: {{secret.db_password}}\n",
+            &map(&[("motd", "hello")]),
+            &map(&[("db_password", "hunter2")]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "motd: hello\npassword: hunter2\n");
+    }
+
+    #[test]
+    fn missing_variable() {
+        let err = render("{{ motd }}", &map(&[]), &map(&[])).unwrap_err();
+        assert!(matches!(err, RenderError::UnknownVariable(name) if name == "motd"));
+    }
+
+    #[test]
+    fn secrets_are_not_read_from_variables() {
+        let err = render(
+            "{{ secret.token }}",
+            &map(&[("secret.token", "leaked"), ("token", "leaked")]),
+            &map(&[]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, RenderError::UnknownSecret(name) if name == "token"));
+    }
+
+    #[test]
+    fn variables_are_not_read_from_secrets() {
+        let err = render("{{ token }}", &map(&[]), &map(&[("token", "leaked")])).unwrap_err();
+        assert!(matches!(err, RenderError::UnknownVariable(name) if name == "token"));
+    }
+
+    #[test]
+    fn unterminated_placeholder() {
+        let err = render("{{ motd", &map(&[("motd", "hello")]), &map(&[])).unwrap_err();
+        assert!(matches!(err, RenderError::Unterminated));
+    }
+
+    #[test]
+    fn lists_referenced_secrets() {
+        let names = referenced_secrets("{{ secret.a }} {{ b }} {{secret.c}} {{ secret.a }}");
+        assert_eq!(names.into_iter().collect::<Vec<_>>(), ["a", "c"]);
+    }
+}
@@ -3,23 +3,43 @@ use sqlx::SqlitePool;
 use uuid::Uuid;
 
 use crate::{
-    assembler::{ArtifactCoordinate, errors::AssemblyError},
-    database::blobs::remove_blob_ref,
+    assembler::{
+        ArtifactCoordinate, AssemblyOptions, archive::ArchiveFormat, errors::AssemblyError,
+        job::AssemblyJob,
+    },
+    database::{
+        blobs::remove_blob_ref, configs::get_assembly_configs, decode_timestamp, decode_uuid,
+        plugins::get_game_versions,
+    },
     errors::Error,
     models::{
-        assembly::{Assembly, AssemblyStatus, ResolvedArtifact},
+        assembly::{
+            Assembly, AssemblyConfig, AssemblyProgress, AssemblyRelease, AssemblyStatus,
+            BuiltArtifact, ResolvedArtifact,
+        },
         blobs::BlobEntityType,
+        configs::ConfigTemplateVersion,
         plugins::{Plugin, PluginSource, PluginVersion},
     },
     storage::filesystem::FilesystemStorage,
 };
 
+/// how long an assembly must still have before it's handed out again
+const REUSE_MARGIN: Duration = Duration::minutes(5);
+
 pub async fn create_assembly(
     pool: &SqlitePool,
     group_id: Uuid,
     artifacts: Vec<ArtifactCoordinate>,
+    options: &AssemblyOptions,
+    configs: &[ConfigTemplateVersion],
+    fingerprint: &str,
 ) -> Result<Uuid, sqlx::Error> {
     let id = Uuid::now_v7();
+    let layout = options.layout.as_str();
+    let format = options.format.as_str();
+    let variables =
+        serde_json::to_string(&options.variables).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
 
     let mut tx = pool.begin().await?;
 
@@ -27,14 +47,33 @@ pub async fn create_assembly(
     let group_id_str = group_id.to_string();
 
     sqlx::query!(
-        "INSERT INTO assemblies (id, group_id)
-         VALUES (?, ?)",
+        "INSERT INTO assemblies (id, group_id, minecraft_version, java_version, variables, layout, filename, format, fingerprint)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
         id_str,
         group_id_str,
+        options.minecraft_version,
+        options.java_version,
+        variables,
+        layout,
+        options.filename,
+        format,
+        fingerprint,
     )
     .execute(&mut *tx)
     .await?;
 
+    for config in configs {
+        let version_id = config.id.to_string();
+        sqlx::query!(
+            "INSERT INTO assembly_configs (assembly_id, template_version_id)
+             VALUES (?, ?)",
+            id_str,
+            version_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
     for artifact in artifacts {
         sqlx::query!(
             "INSERT INTO assembly_artifacts (assembly_id, group_id, artifact_id, version)
@@ -53,31 +92,218 @@ pub async fn create_assembly(
     Ok(id)
 }
 
-pub async fn set_assembly_status(
+/// the newest completed assembly with this fingerprint that hasn't expired yet, pinned
+/// releases never do. a reused assembly's expiry is pushed out to `expires_at`, so it stays
+/// downloadable as long as a fresh build would.
+pub async fn find_reusable_assembly(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    fingerprint: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+    let rows = sqlx::query!(
+        "SELECT id, expires_at, release_name FROM assemblies
+         WHERE group_id = ? AND fingerprint = ? AND status = 'completed'
+         ORDER BY id DESC",
+        group_id_str,
+        fingerprint,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // cleanup may already have picked up rows this close to expiring
+    let cutoff = Utc::now() + REUSE_MARGIN;
+    for row in rows {
+        let current = row
+            .expires_at
+            .as_deref()
+            .map(|s| decode_timestamp("expires_at", s))
+            .transpose()?;
+
+        if row.release_name.is_none() {
+            match current {
+                Some(current) if current > cutoff => {
+                    if expires_at > current {
+                        sqlx::query!(
+                            "UPDATE assemblies SET expires_at = ? WHERE id = ?",
+                            expires_at,
+                            row.id,
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        tx.commit().await?;
+        return decode_uuid("id", &row.id).map(Some);
+    }
+
+    Ok(None)
+}
+
+/// takes the next assembly that is pending, or running with an expired lease (its worker
+/// died mid-build), and leases it to `lease_id` until `lease_expires_at`.
+///
+/// groups already at `max_per_group` live builds are skipped, and the group with the fewest
+/// live builds goes first, so one group's backlog can't starve the others. within a group
+/// it's first come, first served.
+pub async fn claim_next_assembly(
+    pool: &SqlitePool,
+    lease_id: Uuid,
+    lease_expires_at: DateTime<Utc>,
+    max_attempts: u32,
+    max_per_group: u32,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let now = Utc::now();
+    let now_millis = now.timestamp_millis();
+    let lease_id = lease_id.to_string();
+    let lease_expires_at = lease_expires_at.timestamp_millis();
+
+    let id = sqlx::query_scalar!(
+        r#"
+        UPDATE assemblies
+        SET status = 'running', lease_id = ?, lease_expires_at = ?, attempts = attempts + 1,
+            started_at = ?, updated_at = ?, progress = NULL
+        WHERE id = (
+            SELECT a.id
+            FROM assemblies a
+            LEFT JOIN (
+                SELECT group_id, COUNT(*) AS live
+                FROM assemblies
+                WHERE status = 'running' AND lease_expires_at >= ?
+                GROUP BY group_id
+            ) r ON r.group_id = a.group_id
+            WHERE (a.status = 'pending' OR (a.status = 'running' AND a.lease_expires_at < ?))
+              AND a.attempts < ?
+              AND COALESCE(r.live, 0) < ?
+            ORDER BY COALESCE(r.live, 0), a.id
+            LIMIT 1
+        )
+        RETURNING id AS "id!"
+        "#,
+        lease_id,
+        lease_expires_at,
+        now,
+        now,
+        now_millis,
+        now_millis,
+        max_attempts,
+        max_per_group,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    id.map(|id| decode_uuid("id", &id)).transpose()
+}
+
+/// returns false if the lease was lost, i.e. another worker reclaimed the assembly
+pub async fn renew_assembly_lease(
     pool: &SqlitePool,
     assembly_id: Uuid,
-    status: AssemblyStatus,
+    lease_id: Uuid,
+    lease_expires_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let assembly_id = assembly_id.to_string();
+    let lease_id = lease_id.to_string();
+    let lease_expires_at = lease_expires_at.timestamp_millis();
+
+    let result = sqlx::query!(
+        "UPDATE assemblies SET lease_expires_at = ?
+         WHERE id = ? AND lease_id = ? AND status = 'running'",
+        lease_expires_at,
+        assembly_id,
+        lease_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// ignored once the lease is lost, a reclaimed build reports its own progress
+pub async fn set_assembly_progress(
+    pool: &SqlitePool,
+    assembly_id: Uuid,
+    lease_id: Uuid,
+    progress: &AssemblyProgress,
 ) -> Result<(), sqlx::Error> {
     let now = Utc::now();
     let assembly_id = assembly_id.to_string();
+    let lease_id = lease_id.to_string();
+    let progress = serde_json::to_string(progress).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
 
-    match status {
-        AssemblyStatus::Pending => {
-            sqlx::query!(
-                "UPDATE assemblies SET status = 'pending', updated_at = ? WHERE id = ?",
-                now,
-                assembly_id
-            )
-            .execute(pool)
-            .await?;
-        }
-        AssemblyStatus::Running => {
-            sqlx::query!(
-                "UPDATE assemblies SET status = 'running', started_at = ?, updated_at = ? WHERE id = ?",
-                now, now, assembly_id
-            )
-            .execute(pool)
-            .await?;
+    sqlx::query!(
+        "UPDATE assemblies SET progress = ?, updated_at = ?
+         WHERE id = ? AND lease_id = ? AND status = 'running'",
+        progress,
+        now,
+        assembly_id,
+        lease_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// assemblies whose worker crashed `max_attempts` times are given up on. returns the
+/// `(group_id, assembly_id)` of each.
+pub async fn fail_exhausted_assemblies(
+    pool: &SqlitePool,
+    max_attempts: u32,
+) -> Result<Vec<(Uuid, Uuid)>, sqlx::Error> {
+    let now = Utc::now();
+    let now_millis = now.timestamp_millis();
+    let error = format!("assembly abandoned after {max_attempts} attempts");
+
+    let rows = sqlx::query!(
+        r#"UPDATE assemblies
+           SET status = 'failed', completed_at = ?, updated_at = ?, error = ?,
+               lease_id = NULL, lease_expires_at = NULL
+           WHERE status = 'running' AND lease_expires_at < ? AND attempts >= ?
+           RETURNING id AS "id!", group_id"#,
+        now,
+        now,
+        error,
+        now_millis,
+        max_attempts,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok((
+                decode_uuid("group_id", &r.group_id)?,
+                decode_uuid("id", &r.id)?,
+            ))
+        })
+        .collect()
+}
+
+/// records the final status of a leased assembly. returns false if the lease was lost,
+/// in which case the status is left to whoever holds it now.
+pub async fn finish_assembly(
+    pool: &SqlitePool,
+    assembly_id: Uuid,
+    lease_id: Uuid,
+    status: AssemblyStatus,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let assembly_id = assembly_id.to_string();
+    let lease_id = lease_id.to_string();
+
+    let result = match status {
+        AssemblyStatus::Pending | AssemblyStatus::Running | AssemblyStatus::Cancelled => {
+            return Err(sqlx::Error::Protocol(
+                "finish_assembly needs a build result".into(),
+            ));
         }
         AssemblyStatus::Completed {
             blob_id,
@@ -85,26 +311,235 @@ pub async fn set_assembly_status(
         } => {
             let blob_id = blob_id.to_string();
             sqlx::query!(
-                "UPDATE assemblies SET status = 'completed', completed_at = ?, updated_at = ?, blob_id = ?, expires_at = ? WHERE id = ?",
-                now, now, blob_id, expires_at, assembly_id
+                "UPDATE assemblies SET status = 'completed', completed_at = ?, updated_at = ?, blob_id = ?, expires_at = ?,
+                    lease_id = NULL, lease_expires_at = NULL
+                 WHERE id = ? AND lease_id = ?",
+                now, now, blob_id, expires_at, assembly_id, lease_id
             )
             .execute(pool)
-            .await?;
+            .await?
         }
         AssemblyStatus::Failed { error } => {
             let error_msg = error.to_string();
             sqlx::query!(
-                "UPDATE assemblies SET status = 'failed', completed_at = ?, updated_at = ?, error = ? WHERE id = ?",
-                now, now, error_msg, assembly_id
+                "UPDATE assemblies SET status = 'failed', completed_at = ?, updated_at = ?, error = ?,
+                    lease_id = NULL, lease_expires_at = NULL
+                 WHERE id = ? AND lease_id = ?",
+                now, now, error_msg, assembly_id, lease_id
             )
             .execute(pool)
-            .await?;
+            .await?
         }
-    }
+    };
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// cancels a pending or running assembly. clearing the lease makes a running worker's next
+/// renewal fail, which aborts its build. returns false if the assembly had already finished.
+pub async fn cancel_assembly(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let group_id = group_id.to_string();
+    let assembly_id = assembly_id.to_string();
+
+    let result = sqlx::query!(
+        "UPDATE assemblies SET status = 'cancelled', completed_at = ?, updated_at = ?,
+            lease_id = NULL, lease_expires_at = NULL
+         WHERE id = ? AND group_id = ? AND status IN ('pending', 'running')",
+        now,
+        now,
+        assembly_id,
+        group_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// rebuilds the job for a claimed assembly from what `create_assembly` stored
+pub async fn load_assembly_job(
+    pool: &SqlitePool,
+    assembly_id: Uuid,
+) -> Result<Option<AssemblyJob>, sqlx::Error> {
+    let assembly_id_str = assembly_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT group_id, minecraft_version, java_version, variables, layout, filename, format
+        FROM assemblies
+        WHERE id = ?
+        "#,
+        assembly_id_str,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let group_id = decode_uuid("group_id", &row.group_id)?;
+    let configs = get_assembly_configs(pool, assembly_id)
+        .await?
+        .into_iter()
+        .map(|v| v.path)
+        .collect();
+
+    Ok(Some(AssemblyJob {
+        id: assembly_id,
+        group_id,
+        artifacts: get_assembly_artifacts(pool, &assembly_id_str).await?,
+        options: AssemblyOptions {
+            minecraft_version: row.minecraft_version,
+            java_version: row.java_version.map(|v| v as u16),
+            configs,
+            variables: serde_json::from_str(&row.variables).map_err(|e| {
+                sqlx::Error::ColumnDecode {
+                    index: "variables".into(),
+                    source: Box::new(e),
+                }
+            })?,
+            layout: row.layout.parse().map_err(|e| sqlx::Error::ColumnDecode {
+                index: "layout".into(),
+                source: Box::new(e),
+            })?,
+            filename: row.filename,
+            format: row.format.parse().map_err(|e| sqlx::Error::ColumnDecode {
+                index: "format".into(),
+                source: Box::new(e),
+            })?,
+        },
+    }))
+}
+
+async fn get_assembly_artifacts(
+    pool: &SqlitePool,
+    assembly_id: &str,
+) -> Result<Vec<ArtifactCoordinate>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT group_id, artifact_id, version
+        FROM assembly_artifacts
+        WHERE assembly_id = ?
+        "#,
+        assembly_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ArtifactCoordinate {
+            group_id: r.group_id,
+            artifact_id: r.artifact_id,
+            version: r.version,
+        })
+        .collect())
+}
+
+pub async fn set_artifact_checksum(
+    executor: &mut sqlx::SqliteConnection,
+    assembly_id: Uuid,
+    coord: &ArtifactCoordinate,
+    sha256: &str,
+    size_bytes: u64,
+) -> Result<(), sqlx::Error> {
+    let assembly_id_str = assembly_id.to_string();
+    let size_bytes = size_bytes as i64;
+
+    sqlx::query!(
+        "UPDATE assembly_artifacts SET sha256 = ?, size_bytes = ?
+         WHERE assembly_id = ? AND group_id = ? AND artifact_id = ? AND version = ?",
+        sha256,
+        size_bytes,
+        assembly_id_str,
+        coord.group_id,
+        coord.artifact_id,
+        coord.version,
+    )
+    .execute(executor)
+    .await?;
 
     Ok(())
 }
 
+/// the requested artifacts with what was written for them, checksums are None until the
+/// assembly is built
+pub async fn get_built_artifacts(
+    pool: &SqlitePool,
+    assembly_id: Uuid,
+) -> Result<Vec<BuiltArtifact>, sqlx::Error> {
+    let assembly_id_str = assembly_id.to_string();
+
+    let rows = sqlx::query!(
+        "SELECT group_id, artifact_id, version, sha256, size_bytes
+         FROM assembly_artifacts
+         WHERE assembly_id = ?
+         ORDER BY group_id, artifact_id, version",
+        assembly_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| BuiltArtifact {
+            coordinate: ArtifactCoordinate {
+                group_id: r.group_id,
+                artifact_id: r.artifact_id,
+                version: r.version,
+            },
+            sha256: r.sha256,
+            size_bytes: r.size_bytes.map(|s| s as u64),
+        })
+        .collect())
+}
+
+/// the plugin behind each artifact of an assembly in the group, None where the plugin is no
+/// longer attached. empty if there's no such assembly.
+pub async fn get_assembly_plugin_ids(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+) -> Result<Vec<Option<Uuid>>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let assembly_id_str = assembly_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT (
+            SELECT gp.plugin_id FROM plugins p
+            JOIN group_plugins gp ON gp.plugin_id = p.id
+            WHERE p.plugin_group_id = aa.group_id
+              AND p.plugin_artifact_id = aa.artifact_id
+              AND gp.group_id = a.group_id
+            LIMIT 1
+        ) AS "plugin_id?: String"
+        FROM assembly_artifacts aa
+        JOIN assemblies a ON a.id = aa.assembly_id
+        WHERE aa.assembly_id = ? AND a.group_id = ?
+        "#,
+        assembly_id_str,
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            r.plugin_id
+                .as_deref()
+                .map(|id| decode_uuid("plugin_id", id))
+                .transpose()
+        })
+        .collect()
+}
+
 pub async fn get_artifact(
     pool: &SqlitePool,
     coord: &ArtifactCoordinate,
@@ -112,6 +547,9 @@ pub async fn get_artifact(
 ) -> Result<ResolvedArtifact, AssemblyError> {
     let group_id_str = group_id.to_string();
 
+    // one connection for both queries, not two from the pool at once
+    let mut conn = pool.acquire().await?;
+
     let row = sqlx::query!(
         r#"
         SELECT
@@ -123,7 +561,9 @@ pub async fn get_artifact(
             p.external_id,
             pv.id as version_id,
             pv.version,
-            pv.blob_id
+            pv.blob_id,
+            pv.api_version,
+            pv.min_java_version
         FROM plugins p
         JOIN plugin_versions pv ON pv.plugin_id = p.id
         JOIN group_plugins gp ON gp.plugin_id = p.id
@@ -131,13 +571,20 @@ pub async fn get_artifact(
           AND p.plugin_artifact_id = ?
           AND pv.version = ?
           AND gp.group_id = ?
+          AND (
+            gp.is_owner = 1
+            OR EXISTS (
+                SELECT 1 FROM group_plugins o
+                WHERE o.plugin_id = p.id AND o.is_owner = 1 AND o.visibility = 'public'
+            )
+          )
         "#,
         coord.group_id,
         coord.artifact_id,
         coord.version,
         group_id_str,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?
     .ok_or_else(|| {
         AssemblyError::ArtifactNotFound(format!(
@@ -149,19 +596,20 @@ pub async fn get_artifact(
     let plugin_id = row
         .plugin_id
         .as_deref()
-        .ok_or_else(|| AssemblyError::Internal("plugin id missing".into()))
-        .and_then(|s| Uuid::try_parse(s).map_err(|e| AssemblyError::Internal(e.to_string())))?;
+        .ok_or_else(|| AssemblyError::Internal("plugin id missing".into()))?;
+    let plugin_id = decode_uuid("plugin_id", plugin_id)?;
     let version_id = row
         .version_id
         .as_deref()
-        .ok_or_else(|| AssemblyError::Internal("version id missing".into()))
-        .and_then(|s| Uuid::try_parse(s).map_err(|e| AssemblyError::Internal(e.to_string())))?;
+        .ok_or_else(|| AssemblyError::Internal("version id missing".into()))?;
+    let version_id = decode_uuid("version_id", version_id)?;
     let blob_id = row
         .blob_id
         .as_deref()
-        .map(Uuid::try_parse)
-        .transpose()
-        .map_err(|e| AssemblyError::Internal(e.to_string()))?;
+        .map(|id| decode_uuid("blob_id", id))
+        .transpose()?;
+
+    let game_versions = get_game_versions(&mut conn, &version_id.to_string()).await?;
 
     let source = match row.source.as_str() {
         "local" => PluginSource::Local,
@@ -191,6 +639,9 @@ pub async fn get_artifact(
             plugin_id,
             version: row.version,
             blob_id,
+            api_version: row.api_version,
+            game_versions,
+            min_java_version: row.min_java_version.map(|v| v as u16),
         },
     })
 }
@@ -205,7 +656,9 @@ pub async fn get_assembly(
 
     let row = sqlx::query!(
         r#"
-        SELECT id, group_id, status, updated_at, started_at, completed_at, expires_at, error, blob_id
+        SELECT id, group_id, status, updated_at, started_at, completed_at, expires_at, error, blob_id,
+            minecraft_version, java_version, variables, layout, filename, format, progress,
+            release_name
         FROM assemblies
         WHERE id = ? AND group_id = ?
         "#,
@@ -219,96 +672,235 @@ pub async fn get_assembly(
         return Ok(None);
     };
 
-    let artifact_rows = sqlx::query!(
-        r#"
-        SELECT group_id, artifact_id, version
-        FROM assembly_artifacts
-        WHERE assembly_id = ?
-        "#,
-        assembly_id_str
-    )
-    .fetch_all(pool)
-    .await?;
+    let artifacts = get_assembly_artifacts(pool, &assembly_id_str).await?;
 
-    let artifacts = artifact_rows
+    let queue_position = if row.status == "pending" {
+        Some(get_queue_position(pool, &assembly_id_str).await?)
+    } else {
+        None
+    };
+
+    let configs = get_assembly_configs(pool, assembly_id)
+        .await?
         .into_iter()
-        .map(|r| ArtifactCoordinate {
-            group_id: r.group_id,
-            artifact_id: r.artifact_id,
-            version: r.version,
+        .map(|v| AssemblyConfig {
+            path: v.path,
+            revision: v.revision,
         })
         .collect();
 
-    let id = Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::ColumnDecode {
-        index: "id".into(),
-        source: Box::new(e),
-    })?;
-    let group_id = Uuid::parse_str(&row.group_id).map_err(|e| sqlx::Error::ColumnDecode {
-        index: "group_id".into(),
-        source: Box::new(e),
-    })?;
-    let blob_id = row
-        .blob_id
+    let variables =
+        serde_json::from_str(&row.variables).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "variables".into(),
+            source: Box::new(e),
+        })?;
+
+    let progress = row
+        .progress
         .as_deref()
-        .map(Uuid::parse_str)
+        .map(serde_json::from_str)
         .transpose()
         .map_err(|e| sqlx::Error::ColumnDecode {
-            index: "blob_id".into(),
+            index: "progress".into(),
             source: Box::new(e),
         })?;
 
+    let id = decode_uuid("id", &row.id)?;
+    let group_id = decode_uuid("group_id", &row.group_id)?;
+    let blob_id = row
+        .blob_id
+        .as_deref()
+        .map(|id| decode_uuid("blob_id", id))
+        .transpose()?;
+
     Ok(Some(Assembly {
         id,
         group_id,
         status: row.status,
-        updated_at: DateTime::parse_from_rfc3339(&row.updated_at)
-            .map(|dt| dt.with_timezone(&Utc))
-            .map_err(|e| sqlx::Error::ColumnDecode {
-                index: "updated_at".into(),
-                source: Box::new(e),
-            })?,
+        updated_at: decode_timestamp("updated_at", &row.updated_at)?,
         started_at: row
             .started_at
             .as_deref()
-            .map(|s| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc)))
-            .transpose()
-            .map_err(|e| sqlx::Error::ColumnDecode {
-                index: "started_at".into(),
-                source: Box::new(e),
-            })?,
+            .map(|s| decode_timestamp("started_at", s))
+            .transpose()?,
         completed_at: row
             .completed_at
             .as_deref()
-            .map(|s| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc)))
-            .transpose()
-            .map_err(|e| sqlx::Error::ColumnDecode {
-                index: "completed_at".into(),
-                source: Box::new(e),
-            })?,
+            .map(|s| decode_timestamp("completed_at", s))
+            .transpose()?,
         expires_at: row
             .expires_at
             .as_deref()
-            .map(|s| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc)))
-            .transpose()
-            .map_err(|e| sqlx::Error::ColumnDecode {
-                index: "expires_at".into(),
-                source: Box::new(e),
-            })?,
+            .map(|s| decode_timestamp("expires_at", s))
+            .transpose()?,
         error: row.error,
         blob_id,
         artifacts,
+        minecraft_version: row.minecraft_version,
+        java_version: row.java_version.map(|v| v as u16),
+        configs,
+        variables,
+        layout: row.layout.parse().map_err(|e| sqlx::Error::ColumnDecode {
+            index: "layout".into(),
+            source: Box::new(e),
+        })?,
+        filename: row.filename,
+        format: row.format.parse().map_err(|e| sqlx::Error::ColumnDecode {
+            index: "format".into(),
+            source: Box::new(e),
+        })?,
+        queue_position,
+        progress,
+        release: row.release_name,
     }))
 }
 
+/// 1-based position among pending assemblies, by request order. fairness between groups
+/// means this is an estimate of how many builds are ahead, not an exact schedule.
+async fn get_queue_position(pool: &SqlitePool, assembly_id: &str) -> Result<u64, sqlx::Error> {
+    let ahead = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "ahead!: i64" FROM assemblies WHERE status = 'pending' AND id < ?"#,
+        assembly_id,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(ahead as u64 + 1)
+}
+
+/// pins a completed assembly under `name`. returns false if it isn't completed, is already
+/// pinned, or the name is taken in the group.
+pub async fn pin_release(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let group_id_str = group_id.to_string();
+    let assembly_id_str = assembly_id.to_string();
+
+    let result = sqlx::query!(
+        "UPDATE OR IGNORE assemblies SET release_name = ?, release_pinned_at = ?, updated_at = ?
+         WHERE id = ? AND group_id = ? AND status = 'completed' AND release_name IS NULL",
+        name,
+        now,
+        now,
+        assembly_id_str,
+        group_id_str,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// the assembly goes back to expiring at its original `expires_at`, which may already have
+/// passed. returns the assembly id, None if there was no such release.
+pub async fn unpin_release(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let now = Utc::now();
+    let group_id_str = group_id.to_string();
+
+    let id = sqlx::query_scalar!(
+        r#"UPDATE assemblies SET release_name = NULL, release_pinned_at = NULL, updated_at = ?
+           WHERE group_id = ? AND release_name = ?
+           RETURNING id AS "id!""#,
+        now,
+        group_id_str,
+        name,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    id.map(|id| decode_uuid("id", &id)).transpose()
+}
+
+pub async fn list_releases(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<AssemblyRelease>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"SELECT id, release_name AS "name!", release_pinned_at AS "pinned_at!"
+           FROM assemblies
+           WHERE group_id = ? AND release_name IS NOT NULL
+           ORDER BY release_pinned_at DESC"#,
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(AssemblyRelease {
+                name: r.name,
+                assembly_id: decode_uuid("id", &r.id)?,
+                pinned_at: decode_timestamp("release_pinned_at", &r.pinned_at)?,
+            })
+        })
+        .collect()
+}
+
+pub async fn get_release_assembly_id(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let id = sqlx::query_scalar!(
+        "SELECT id FROM assemblies WHERE group_id = ? AND release_name = ?",
+        group_id_str,
+        name,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    id.map(|id| decode_uuid("id", &id)).transpose()
+}
+
+pub async fn get_assembly_format(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+) -> Result<Option<ArchiveFormat>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let assembly_id_str = assembly_id.to_string();
+
+    let format = sqlx::query_scalar!(
+        "SELECT format FROM assemblies WHERE id = ? AND group_id = ?",
+        assembly_id_str,
+        group_id_str,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    format
+        .map(|f| f.parse())
+        .transpose()
+        .map_err(|e: AssemblyError| sqlx::Error::ColumnDecode {
+            index: "format".into(),
+            source: Box::new(e),
+        })
+}
+
 pub async fn get_assembly_status(
     pool: &SqlitePool,
+    group_id: Uuid,
     assembly_id: Uuid,
 ) -> Result<Option<AssemblyStatus>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
     let assembly_id_str = assembly_id.to_string();
 
     let row = sqlx::query!(
-        "SELECT status, blob_id, expires_at, error FROM assemblies WHERE id = ?",
-        assembly_id_str
+        "SELECT status, blob_id, expires_at, error FROM assemblies WHERE id = ? AND group_id = ?",
+        assembly_id_str,
+        group_id_str,
     )
     .fetch_optional(pool)
     .await?;
@@ -324,7 +916,8 @@ pub async fn get_assembly_status(
             blob_id: row
                 .blob_id
                 .as_deref()
-                .and_then(|s| Uuid::parse_str(s).ok())
+                .map(|s| decode_uuid("blob_id", s))
+                .transpose()?
                 .ok_or_else(|| sqlx::Error::ColumnDecode {
                     index: "blob_id".into(),
                     source: "completed assembly missing blob_id".into(),
@@ -332,12 +925,8 @@ pub async fn get_assembly_status(
             expires_at: row
                 .expires_at
                 .as_deref()
-                .map(|s| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc)))
-                .transpose()
-                .map_err(|e| sqlx::Error::ColumnDecode {
-                    index: "expires_at".into(),
-                    source: Box::new(e),
-                })?
+                .map(|s| decode_timestamp("expires_at", s))
+                .transpose()?
                 .ok_or_else(|| sqlx::Error::ColumnDecode {
                     index: "expires_at".into(),
                     source: "completed assembly missing expires_at".into(),
@@ -346,6 +935,7 @@ pub async fn get_assembly_status(
         "failed" => AssemblyStatus::Failed {
             error: AssemblyError::Internal(row.error.unwrap_or_else(|| "unknown error".into())),
         },
+        "cancelled" => AssemblyStatus::Cancelled,
         s => {
             return Err(sqlx::Error::ColumnDecode {
                 index: "status".into(),
@@ -364,24 +954,19 @@ pub async fn cleanup_expired_assemblies(
     let expired = sqlx::query!(
         "SELECT id, group_id, blob_id FROM assemblies
          WHERE status = 'completed' AND expires_at < datetime('now')
-         AND blob_id IS NOT NULL"
+         AND blob_id IS NOT NULL AND release_name IS NULL"
     )
     .fetch_all(pool)
     .await?;
 
     for row in expired {
-        let assembly_id = Uuid::try_parse(&row.id).map_err(|_| sqlx::Error::ColumnDecode {
-            index: "id".into(),
-            source: "invalid assembly id".into(),
-        })?;
-        let group_id = Uuid::try_parse(&row.group_id).map_err(|_| sqlx::Error::ColumnDecode {
-            index: "group_id".into(),
-            source: "invalid group_id".into(),
-        })?;
+        let assembly_id = decode_uuid("id", &row.id)?;
+        let group_id = decode_uuid("group_id", &row.group_id)?;
         let blob_id = row
             .blob_id
             .as_deref()
-            .and_then(|s| Uuid::parse_str(s).ok())
+            .map(|s| decode_uuid("blob_id", s))
+            .transpose()?
             .ok_or_else(|| sqlx::Error::ColumnDecode {
                 index: "blob_id".into(),
                 source: "completed assembly missing blob_id".into(),
@@ -411,6 +996,8 @@ pub async fn cleanup_expired_assemblies(
     Ok(())
 }
 
+/// only finished, unsuccessful rows; pending and running jobs are left to the lease and
+/// attempt limits however old they are
 pub async fn cleanup_old_assemblies(pool: &SqlitePool, older_than: Duration) -> Result<(), Error> {
     let threshold = Utc::now() - older_than;
     let threshold_uuid = Uuid::new_v7(uuid::Timestamp::from_unix(
@@ -421,7 +1008,7 @@ pub async fn cleanup_old_assemblies(pool: &SqlitePool, older_than: Duration) ->
     let threshold_str = threshold_uuid.to_string();
 
     sqlx::query!(
-        "DELETE FROM assemblies WHERE status != 'completed' AND id < ?",
+        "DELETE FROM assemblies WHERE status IN ('failed', 'cancelled') AND id < ?",
         threshold_str,
     )
     .execute(pool)
@@ -7,8 +7,8 @@ use crate::{
     storage::{LocalStorage, filesystem::FilesystemStorage},
 };
 
-pub async fn find_by_sha256(
-    pool: &SqlitePool,
+pub async fn find_by_sha256<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
     sha256: String,
 ) -> Result<Option<Blob>, sqlx::Error> {
     sqlx::query_as::<_, Blob>(
@@ -19,6 +19,19 @@ pub async fn find_by_sha256(
              GROUP BY b.id",
     )
     .bind(sha256)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn get_blob(pool: &SqlitePool, blob_id: Uuid) -> Result<Option<Blob>, sqlx::Error> {
+    sqlx::query_as::<_, Blob>(
+        "SELECT b.*, COUNT(br.blob_id) AS ref_count
+             FROM blobs b
+             LEFT JOIN blob_refs br ON br.blob_id = b.id
+             WHERE b.id = ?
+             GROUP BY b.id",
+    )
+    .bind(blob_id.to_string())
     .fetch_optional(pool)
     .await
 }
@@ -0,0 +1,192 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{decode_timestamp, decode_uuid},
+    models::configs::{ConfigTemplate, ConfigTemplateVersion},
+};
+
+/// stores a new revision of the template at `path`, creating the template on first upload.
+pub async fn create_config_version(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    path: &str,
+    content: &str,
+) -> Result<ConfigTemplateVersion, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let new_template_id = Uuid::now_v7().to_string();
+    let version_id = Uuid::now_v7();
+    let version_id_str = version_id.to_string();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "INSERT OR IGNORE INTO config_templates (id, group_id, path) VALUES (?, ?, ?)",
+        new_template_id,
+        group_id_str,
+        path,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let template_id = sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM config_templates WHERE group_id = ? AND path = ?"#,
+        group_id_str,
+        path,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let revision = sqlx::query_scalar!(
+        r#"SELECT COALESCE(MAX(revision), 0) + 1 AS "revision!: i64"
+           FROM config_template_versions WHERE template_id = ?"#,
+        template_id,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let created_at = sqlx::query_scalar!(
+        r#"INSERT INTO config_template_versions (id, template_id, revision, content)
+           VALUES (?, ?, ?, ?)
+           RETURNING created_at AS "created_at!""#,
+        version_id_str,
+        template_id,
+        revision,
+        content,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(ConfigTemplateVersion {
+        id: version_id,
+        template_id: decode_uuid("template_id", &template_id)?,
+        path: path.to_string(),
+        revision: revision as u32,
+        content: content.to_string(),
+        created_at: decode_timestamp("created_at", &created_at)?,
+    })
+}
+
+pub async fn list_config_templates(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<ConfigTemplate>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            ct.id AS "id!",
+            ct.path,
+            MAX(ctv.revision) AS "latest_revision!: i64"
+        FROM config_templates ct
+        JOIN config_template_versions ctv ON ctv.template_id = ct.id
+        WHERE ct.group_id = ?
+        GROUP BY ct.id
+        ORDER BY ct.path
+        "#,
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(ConfigTemplate {
+                id: decode_uuid("id", &r.id)?,
+                group_id,
+                path: r.path,
+                latest_revision: r.latest_revision as u32,
+            })
+        })
+        .collect()
+}
+
+/// fetches a specific revision of a template, or the latest one if `revision` is None.
+pub async fn get_config_version(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    path: &str,
+    revision: Option<u32>,
+) -> Result<Option<ConfigTemplateVersion>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            ctv.id AS "id!",
+            ctv.template_id,
+            ct.path,
+            ctv.revision,
+            ctv.content,
+            ctv.created_at
+        FROM config_template_versions ctv
+        JOIN config_templates ct ON ct.id = ctv.template_id
+        WHERE ct.group_id = ? AND ct.path = ? AND (? IS NULL OR ctv.revision = ?)
+        ORDER BY ctv.revision DESC
+        LIMIT 1
+        "#,
+        group_id_str,
+        path,
+        revision,
+        revision,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(r) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(ConfigTemplateVersion {
+        id: decode_uuid("id", &r.id)?,
+        template_id: decode_uuid("template_id", &r.template_id)?,
+        path: r.path,
+        revision: r.revision as u32,
+        content: r.content,
+        created_at: decode_timestamp("created_at", &r.created_at)?,
+    }))
+}
+
+/// the template revisions pinned to an assembly when it was requested
+pub async fn get_assembly_configs(
+    pool: &SqlitePool,
+    assembly_id: Uuid,
+) -> Result<Vec<ConfigTemplateVersion>, sqlx::Error> {
+    let assembly_id_str = assembly_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            ctv.id AS "id!",
+            ctv.template_id,
+            ct.path,
+            ctv.revision,
+            ctv.content,
+            ctv.created_at
+        FROM assembly_configs ac
+        JOIN config_template_versions ctv ON ctv.id = ac.template_version_id
+        JOIN config_templates ct ON ct.id = ctv.template_id
+        WHERE ac.assembly_id = ?
+        ORDER BY ct.path
+        "#,
+        assembly_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(ConfigTemplateVersion {
+                id: decode_uuid("id", &r.id)?,
+                template_id: decode_uuid("template_id", &r.template_id)?,
+                path: r.path,
+                revision: r.revision as u32,
+                content: r.content,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+            })
+        })
+        .collect()
+}
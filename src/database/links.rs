@@ -0,0 +1,237 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    assembler::ArtifactCoordinate,
+    database::{decode_timestamp, decode_uuid},
+    models::links::{DownloadLink, LinkTarget, RedeemedLink},
+};
+
+fn decode_ip(value: Option<String>) -> Result<Option<IpAddr>, sqlx::Error> {
+    value
+        .map(|ip| {
+            ip.parse().map_err(|e| sqlx::Error::ColumnDecode {
+                index: "bound_ip".into(),
+                source: Box::new(e),
+            })
+        })
+        .transpose()
+}
+
+fn decode_target(
+    assembly_id: Option<String>,
+    artifact: Option<String>,
+) -> Result<LinkTarget, sqlx::Error> {
+    if let Some(id) = assembly_id {
+        return Ok(LinkTarget::Assembly(decode_uuid("assembly_id", &id)?));
+    }
+
+    let artifact = artifact.unwrap_or_default();
+    match artifact.splitn(3, ':').collect::<Vec<_>>().as_slice() {
+        [group_id, artifact_id, version] => Ok(LinkTarget::Artifact(ArtifactCoordinate {
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            version: version.to_string(),
+        })),
+        _ => Err(sqlx::Error::ColumnDecode {
+            index: "artifact".into(),
+            source: format!("invalid artifact coordinate {artifact}").into(),
+        }),
+    }
+}
+
+pub async fn create_link(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    target: &LinkTarget,
+    created_by: Uuid,
+    expires_at: i64,
+    bound_ip: Option<IpAddr>,
+    max_uses: Option<u32>,
+) -> Result<Uuid, sqlx::Error> {
+    let id = Uuid::now_v7();
+    let id_str = id.to_string();
+    let group_id_str = group_id.to_string();
+    let created_by = created_by.to_string();
+    let bound_ip = bound_ip.map(|ip| ip.to_string());
+    let (assembly_id, artifact) = match target {
+        LinkTarget::Assembly(id) => (Some(id.to_string()), None),
+        LinkTarget::Artifact(coord) => (None, Some(coord.to_string())),
+    };
+
+    sqlx::query!(
+        "INSERT INTO download_links
+            (id, group_id, assembly_id, artifact, created_by, expires_at, bound_ip, max_uses)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        id_str,
+        group_id_str,
+        assembly_id,
+        artifact,
+        created_by,
+        expires_at,
+        bound_ip,
+        max_uses,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(id)
+}
+
+pub async fn get_link(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    id: Uuid,
+) -> Result<Option<DownloadLink>, sqlx::Error> {
+    Ok(list_links_where(pool, group_id, Some(id)).await?.pop())
+}
+
+/// newest first
+pub async fn list_links(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<DownloadLink>, sqlx::Error> {
+    list_links_where(pool, group_id, None).await
+}
+
+async fn list_links_where(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    id: Option<Uuid>,
+) -> Result<Vec<DownloadLink>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let id_str = id.map(|id| id.to_string());
+
+    let rows = sqlx::query!(
+        "SELECT id, group_id, assembly_id, artifact, created_by, expires_at, bound_ip, max_uses,
+            uses, revoked_at, created_at
+         FROM download_links
+         WHERE group_id = ? AND (? IS NULL OR id = ?)
+         ORDER BY id DESC",
+        group_id_str,
+        id_str,
+        id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(DownloadLink {
+                id: decode_uuid("id", &r.id)?,
+                group_id: decode_uuid("group_id", &r.group_id)?,
+                assembly_id: r
+                    .assembly_id
+                    .as_deref()
+                    .map(|id| decode_uuid("assembly_id", id))
+                    .transpose()?,
+                artifact: r.artifact,
+                created_by: decode_uuid("created_by", &r.created_by)?,
+                expires_at: DateTime::from_timestamp(r.expires_at, 0).unwrap_or_default(),
+                bound_ip: decode_ip(r.bound_ip)?,
+                max_uses: r.max_uses.map(|n| n as u32),
+                uses: r.uses as u32,
+                revoked_at: r
+                    .revoked_at
+                    .as_deref()
+                    .map(|at| decode_timestamp("revoked_at", at))
+                    .transpose()?,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+            })
+        })
+        .collect()
+}
+
+/// returns false if the group has no such link or it was already revoked
+pub async fn revoke_link(pool: &SqlitePool, group_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let id_str = id.to_string();
+
+    let result = sqlx::query!(
+        "UPDATE download_links SET revoked_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+         WHERE id = ? AND group_id = ? AND revoked_at IS NULL",
+        id_str,
+        group_id_str,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// what the link points at, None if it's revoked, expired or used up
+pub async fn get_usable_link(
+    pool: &SqlitePool,
+    id: Uuid,
+    now: i64,
+) -> Result<Option<RedeemedLink>, sqlx::Error> {
+    let id_str = id.to_string();
+
+    let row = sqlx::query!(
+        "SELECT group_id, assembly_id, artifact FROM download_links
+         WHERE id = ? AND revoked_at IS NULL AND expires_at > ?
+            AND (max_uses IS NULL OR uses < max_uses)",
+        id_str,
+        now,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|r| {
+        Ok(RedeemedLink {
+            group_id: decode_uuid("group_id", &r.group_id)?,
+            target: decode_target(r.assembly_id, r.artifact)?,
+        })
+    })
+    .transpose()
+}
+
+/// counts one use of the link, false if it was used up in the meantime
+pub async fn consume_link(pool: &SqlitePool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let id_str = id.to_string();
+
+    let result = sqlx::query!(
+        "UPDATE download_links SET uses = uses + 1
+         WHERE id = ? AND (max_uses IS NULL OR uses < max_uses)",
+        id_str,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// the expiry and ip binding a link was signed with, None if there's no such link
+pub async fn get_link_binding(
+    pool: &SqlitePool,
+    id: Uuid,
+) -> Result<Option<(i64, Option<IpAddr>)>, sqlx::Error> {
+    let id_str = id.to_string();
+
+    let row = sqlx::query!(
+        "SELECT expires_at, bound_ip FROM download_links WHERE id = ?",
+        id_str,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|r| Ok((r.expires_at, decode_ip(r.bound_ip)?)))
+        .transpose()
+}
+
+/// links are kept for a while after they expire so the group can still see them used
+pub async fn cleanup_expired_links(
+    pool: &SqlitePool,
+    older_than: chrono::Duration,
+) -> Result<u64, sqlx::Error> {
+    let threshold = (Utc::now() - older_than).timestamp();
+
+    let result = sqlx::query!("DELETE FROM download_links WHERE expires_at < ?", threshold,)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -76,6 +76,29 @@ pub async fn machine_key_has_wide_permission(
     Ok(row.is_some())
 }
 
+/// wide or for any single resource
+pub async fn machine_key_has_any_permission(
+    pool: &SqlitePool,
+    key_id: Uuid,
+    resource_type: ResourceType,
+    action: Action,
+) -> Result<bool, sqlx::Error> {
+    let key_id = key_id.to_string();
+
+    let row = sqlx::query_as::<_, Found>(
+        "SELECT 1 FROM machine_key_permissions
+         WHERE key_id = ? AND resource_type = ? AND action = ?
+         LIMIT 1",
+    )
+    .bind(key_id)
+    .bind(resource_type)
+    .bind(action)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.is_some())
+}
+
 pub struct CreatedMachineKey {
     pub key: PublicMachineKey,
     pub secret: String,
@@ -1,7 +1,34 @@
 pub mod assembly;
 pub mod blobs;
+pub mod configs;
 pub mod groups;
+pub mod links;
 pub mod machine_keys;
 pub mod plugins;
+pub mod profiles;
+pub mod secrets;
 pub mod settings;
+pub mod signing;
 pub mod users;
+pub mod webhooks;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// uuids are stored as text
+pub(crate) fn decode_uuid(index: &str, value: &str) -> Result<Uuid, sqlx::Error> {
+    Uuid::parse_str(value).map_err(|e| sqlx::Error::ColumnDecode {
+        index: index.to_string(),
+        source: Box::new(e),
+    })
+}
+
+/// timestamps are stored as rfc3339 text
+pub(crate) fn decode_timestamp(index: &str, value: &str) -> Result<DateTime<Utc>, sqlx::Error> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| sqlx::Error::ColumnDecode {
+            index: index.to_string(),
+            source: Box::new(e),
+        })
+}
@@ -1,9 +1,12 @@
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
 use crate::{
-    database::blobs,
-    models::{blobs::BlobEntityType, plugins::PluginVersion},
+    database::{blobs, decode_timestamp, decode_uuid},
+    models::{
+        blobs::BlobEntityType,
+        plugins::{PluginOwner, PluginShare, PluginVersion, SharedPlugin, Visibility},
+    },
 };
 
 pub struct CreateLocalPluginOptions {
@@ -17,6 +20,9 @@ pub struct CreateLocalPluginOptions {
     pub sha256: String,
     pub size_bytes: u64,
     pub is_new_blob: bool,
+    pub api_version: Option<String>,
+    pub game_versions: Vec<String>,
+    pub min_java_version: Option<u16>,
 }
 
 pub async fn create_local_plugin(
@@ -60,16 +66,29 @@ pub async fn create_local_plugin(
     .await?;
 
     sqlx::query!(
-        "INSERT INTO plugin_versions (id, plugin_id, version, blob_id)
-         VALUES (?, ?, ?, ?)",
+        "INSERT INTO plugin_versions (id, plugin_id, version, blob_id, api_version, min_java_version)
+         VALUES (?, ?, ?, ?, ?, ?)",
         version_id,
         plugin_id,
         input.version,
         blob_id,
+        input.api_version,
+        input.min_java_version,
     )
     .execute(&mut *tx)
     .await?;
 
+    for game_version in input.game_versions {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO plugin_version_game_versions (version_id, game_version)
+             VALUES (?, ?)",
+            version_id,
+            game_version,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
     sqlx::query!(
         "INSERT INTO group_plugins (group_id, plugin_id, is_owner, visibility)
          VALUES (?, ?, 1, 'private')",
@@ -92,13 +111,17 @@ pub async fn get_plugin_version(
 ) -> Result<Option<PluginVersion>, sqlx::Error> {
     let group_id_str = group_id.to_string();
 
+    let mut conn = pool.acquire().await?;
+
     let row = sqlx::query!(
         r#"
         SELECT
             pv.id AS "id!",
             pv.plugin_id AS "plugin_id!",
             pv.version AS "version!",
-            pv.blob_id AS blob_id
+            pv.blob_id AS blob_id,
+            pv.api_version AS api_version,
+            pv.min_java_version AS min_java_version
         FROM plugin_versions pv
         JOIN plugins p ON p.id  = pv.plugin_id
         JOIN group_plugins gp ON gp.plugin_id = p.id
@@ -112,34 +135,250 @@ pub async fn get_plugin_version(
         plugin_artifact_id,
         version,
     )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(r) = row else {
+        return Ok(None);
+    };
+
+    let id = decode_uuid("id", &r.id)?;
+    let plugin_id = decode_uuid("plugin_id", &r.plugin_id)?;
+    let blob_id = r
+        .blob_id
+        .as_deref()
+        .map(|blob_id| decode_uuid("blob_id", blob_id))
+        .transpose()?;
+
+    let game_versions = get_game_versions(&mut conn, &r.id).await?;
+
+    Ok(Some(PluginVersion {
+        id,
+        plugin_id,
+        version: r.version,
+        blob_id,
+        api_version: r.api_version,
+        game_versions,
+        min_java_version: r.min_java_version.map(|v| v as u16),
+    }))
+}
+
+pub async fn get_game_versions(
+    executor: &mut SqliteConnection,
+    version_id: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT game_version FROM plugin_version_game_versions WHERE version_id = ? ORDER BY game_version",
+        version_id,
+    )
+    .fetch_all(&mut *executor)
+    .await
+}
+
+pub async fn get_plugin_owner(
+    pool: &SqlitePool,
+    plugin_id: Uuid,
+) -> Result<Option<PluginOwner>, sqlx::Error> {
+    let plugin_id = plugin_id.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT group_id AS "group_id!", visibility AS "visibility!"
+        FROM group_plugins
+        WHERE plugin_id = ? AND is_owner = 1
+        "#,
+        plugin_id,
+    )
     .fetch_optional(pool)
     .await?;
 
     row.map(|r| {
-        let id = Uuid::parse_str(&r.id).map_err(|e| sqlx::Error::ColumnDecode {
-            index: "id".to_string(),
-            source: Box::new(e),
-        })?;
-        let plugin_id = Uuid::parse_str(&r.plugin_id).map_err(|e| sqlx::Error::ColumnDecode {
-            index: "plugin_id".to_string(),
-            source: Box::new(e),
-        })?;
-        let blob_id = r
-            .blob_id
-            .as_deref()
-            .map(Uuid::parse_str)
-            .transpose()
-            .map_err(|e| sqlx::Error::ColumnDecode {
-                index: "blob_id".to_string(),
-                source: Box::new(e),
-            })?;
-
-        Ok(PluginVersion {
-            id,
-            plugin_id,
-            version: r.version,
-            blob_id,
+        Ok(PluginOwner {
+            group_id: decode_uuid("group_id", &r.group_id)?,
+            visibility: match r.visibility.as_str() {
+                "public" => Visibility::Public,
+                _ => Visibility::Private,
+            },
         })
     })
     .transpose()
 }
+
+/// making a plugin private detaches it from every other group, returns how many lost access
+pub async fn set_plugin_visibility(
+    pool: &SqlitePool,
+    plugin_id: Uuid,
+    visibility: Visibility,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let plugin_id = plugin_id.to_string();
+    let visibility = visibility.as_str();
+
+    sqlx::query!(
+        "UPDATE group_plugins SET visibility = ? WHERE plugin_id = ? AND is_owner = 1",
+        visibility,
+        plugin_id,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let detached = if visibility == Visibility::Private.as_str() {
+        sqlx::query!(
+            "DELETE FROM group_plugins WHERE plugin_id = ? AND is_owner = 0",
+            plugin_id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+    } else {
+        0
+    };
+
+    tx.commit().await?;
+    Ok(detached)
+}
+
+/// public plugins owned by other groups
+pub async fn list_shared_plugins(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<SharedPlugin>, sqlx::Error> {
+    let group_id = group_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            p.id AS "id!",
+            p.plugin_group_id AS "plugin_group_id!",
+            p.plugin_artifact_id AS "plugin_artifact_id!",
+            o.group_id AS "owner_group_id!",
+            g.name AS "owner_group_name!",
+            EXISTS (
+                SELECT 1 FROM group_plugins a WHERE a.plugin_id = p.id AND a.group_id = ?
+            ) AS "attached!: bool"
+        FROM group_plugins o
+        JOIN plugins p ON p.id = o.plugin_id
+        JOIN groups g ON g.id = o.group_id
+        WHERE o.is_owner = 1 AND o.visibility = 'public' AND o.group_id != ?
+        ORDER BY p.plugin_group_id, p.plugin_artifact_id
+        "#,
+        group_id,
+        group_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(SharedPlugin {
+                id: decode_uuid("id", &r.id)?,
+                plugin_group_id: r.plugin_group_id,
+                plugin_artifact_id: r.plugin_artifact_id,
+                owner_group_id: decode_uuid("owner_group_id", &r.owner_group_id)?,
+                owner_group_name: r.owner_group_name,
+                attached: r.attached,
+            })
+        })
+        .collect()
+}
+
+/// `plugin_group_id:plugin_artifact_id` of a plugin the group already has under the same name
+pub async fn find_plugin_name_clash(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    let group_id = group_id.to_string();
+    let plugin_id = plugin_id.to_string();
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT p.plugin_group_id || ':' || p.plugin_artifact_id AS "name!: String"
+        FROM group_plugins gp
+        JOIN plugins p ON p.id = gp.plugin_id
+        JOIN plugins s
+          ON s.plugin_group_id = p.plugin_group_id
+         AND s.plugin_artifact_id = p.plugin_artifact_id
+        WHERE gp.group_id = ? AND s.id = ?
+        LIMIT 1
+        "#,
+        group_id,
+        plugin_id,
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// attaches the plugin without copying its blobs, only while the owner has it public
+pub async fn attach_plugin(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let group_id = group_id.to_string();
+    let plugin_id = plugin_id.to_string();
+
+    let result = sqlx::query!(
+        "INSERT INTO group_plugins (group_id, plugin_id, is_owner, visibility)
+         SELECT ?, plugin_id, 0, 'public' FROM group_plugins
+         WHERE plugin_id = ? AND is_owner = 1 AND visibility = 'public'
+         ON CONFLICT (group_id, plugin_id) DO NOTHING",
+        group_id,
+        plugin_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// never removes the owner
+pub async fn detach_plugin(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let group_id = group_id.to_string();
+    let plugin_id = plugin_id.to_string();
+
+    let result = sqlx::query!(
+        "DELETE FROM group_plugins WHERE group_id = ? AND plugin_id = ? AND is_owner = 0",
+        group_id,
+        plugin_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list_plugin_shares(
+    pool: &SqlitePool,
+    plugin_id: Uuid,
+) -> Result<Vec<PluginShare>, sqlx::Error> {
+    let plugin_id = plugin_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT gp.group_id AS "group_id!", g.name AS "group_name!", gp.attached_at AS "attached_at!"
+        FROM group_plugins gp
+        JOIN groups g ON g.id = gp.group_id
+        WHERE gp.plugin_id = ? AND gp.is_owner = 0
+        ORDER BY gp.attached_at
+        "#,
+        plugin_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(PluginShare {
+                group_id: decode_uuid("group_id", &r.group_id)?,
+                group_name: r.group_name,
+                attached_at: decode_timestamp("attached_at", &r.attached_at)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,239 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    assembler::{ArtifactCoordinate, AssemblyOptions},
+    database::{decode_timestamp, decode_uuid},
+    models::assembly::{AssemblyProfile, ProfileRun},
+};
+
+fn decode_artifacts(value: &str) -> Result<Vec<ArtifactCoordinate>, sqlx::Error> {
+    let coords: Vec<String> =
+        serde_json::from_str(value).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "artifacts".into(),
+            source: Box::new(e),
+        })?;
+
+    coords
+        .iter()
+        .map(
+            |coord| match coord.splitn(3, ':').collect::<Vec<_>>().as_slice() {
+                [group_id, artifact_id, version] => Ok(ArtifactCoordinate {
+                    group_id: group_id.to_string(),
+                    artifact_id: artifact_id.to_string(),
+                    version: version.to_string(),
+                }),
+                _ => Err(sqlx::Error::ColumnDecode {
+                    index: "artifacts".into(),
+                    source: format!("invalid artifact coordinate {coord}").into(),
+                }),
+            },
+        )
+        .collect()
+}
+
+struct ProfileRow {
+    id: String,
+    name: String,
+    artifacts: String,
+    options: String,
+    created_by: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<ProfileRow> for AssemblyProfile {
+    type Error = sqlx::Error;
+
+    fn try_from(row: ProfileRow) -> Result<Self, Self::Error> {
+        Ok(AssemblyProfile {
+            id: decode_uuid("id", &row.id)?,
+            name: row.name,
+            artifacts: decode_artifacts(&row.artifacts)?,
+            options: serde_json::from_str(&row.options).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "options".into(),
+                source: Box::new(e),
+            })?,
+            created_by: decode_uuid("created_by", &row.created_by)?,
+            created_at: decode_timestamp("created_at", &row.created_at)?,
+            updated_at: decode_timestamp("updated_at", &row.updated_at)?,
+        })
+    }
+}
+
+/// creates the profile or replaces its contents, keeping its id and history
+pub async fn save_profile(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+    artifacts: &[ArtifactCoordinate],
+    options: &AssemblyOptions,
+    created_by: Uuid,
+) -> Result<AssemblyProfile, sqlx::Error> {
+    let id = Uuid::now_v7().to_string();
+    let group_id = group_id.to_string();
+    let created_by = created_by.to_string();
+    let artifacts =
+        serde_json::to_string(&artifacts.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let options = serde_json::to_string(options).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+    sqlx::query_as!(
+        ProfileRow,
+        r#"
+        INSERT INTO assembly_profiles (id, group_id, name, artifacts, options, created_by)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (group_id, name) DO UPDATE SET
+            artifacts = excluded.artifacts,
+            options = excluded.options,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+        RETURNING
+            id AS "id!", name AS "name!", artifacts AS "artifacts!", options AS "options!",
+            created_by AS "created_by!", created_at AS "created_at!", updated_at AS "updated_at!"
+        "#,
+        id,
+        group_id,
+        name,
+        artifacts,
+        options,
+        created_by,
+    )
+    .fetch_one(pool)
+    .await?
+    .try_into()
+}
+
+pub async fn get_profile(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<Option<AssemblyProfile>, sqlx::Error> {
+    let group_id = group_id.to_string();
+
+    sqlx::query_as!(
+        ProfileRow,
+        r#"
+        SELECT
+            id AS "id!", name, artifacts, options, created_by, created_at, updated_at
+        FROM assembly_profiles
+        WHERE group_id = ? AND name = ?
+        "#,
+        group_id,
+        name,
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(AssemblyProfile::try_from)
+    .transpose()
+}
+
+pub async fn list_profiles(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<AssemblyProfile>, sqlx::Error> {
+    let group_id = group_id.to_string();
+
+    sqlx::query_as!(
+        ProfileRow,
+        r#"
+        SELECT
+            id AS "id!", name, artifacts, options, created_by, created_at, updated_at
+        FROM assembly_profiles
+        WHERE group_id = ?
+        ORDER BY name
+        "#,
+        group_id,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(AssemblyProfile::try_from)
+    .collect()
+}
+
+/// the history goes with it
+pub async fn delete_profile(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let group_id = group_id.to_string();
+
+    let result = sqlx::query!(
+        "DELETE FROM assembly_profiles WHERE group_id = ? AND name = ?",
+        group_id,
+        name,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn record_run(
+    pool: &SqlitePool,
+    profile_id: Uuid,
+    assembly_id: Uuid,
+    requested_by: Uuid,
+    reused: bool,
+) -> Result<(), sqlx::Error> {
+    let id = Uuid::now_v7().to_string();
+    let profile_id = profile_id.to_string();
+    let assembly_id = assembly_id.to_string();
+    let requested_by = requested_by.to_string();
+
+    sqlx::query!(
+        "INSERT INTO assembly_profile_runs (id, profile_id, assembly_id, requested_by, reused)
+         VALUES (?, ?, ?, ?, ?)",
+        id,
+        profile_id,
+        assembly_id,
+        requested_by,
+        reused,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// newest first
+pub async fn list_runs(
+    pool: &SqlitePool,
+    profile_id: Uuid,
+    limit: u32,
+) -> Result<Vec<ProfileRun>, sqlx::Error> {
+    let profile_id = profile_id.to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            r.assembly_id,
+            r.requested_by,
+            r.reused AS "reused!: bool",
+            a.status AS "status?",
+            r.created_at
+        FROM assembly_profile_runs r
+        LEFT JOIN assemblies a ON a.id = r.assembly_id
+        WHERE r.profile_id = ?
+        ORDER BY r.id DESC
+        LIMIT ?
+        "#,
+        profile_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(ProfileRun {
+                assembly_id: decode_uuid("assembly_id", &r.assembly_id)?,
+                requested_by: decode_uuid("requested_by", &r.requested_by)?,
+                reused: r.reused,
+                status: r.status,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,98 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::decode_timestamp,
+    {models::secrets::GroupSecret, secrets::EncryptedSecret},
+};
+
+pub async fn upsert_secret(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+    secret: &EncryptedSecret,
+) -> Result<(), sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    sqlx::query!(
+        "INSERT INTO group_secrets (group_id, name, nonce, ciphertext)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT (group_id, name) DO UPDATE SET
+            nonce = excluded.nonce,
+            ciphertext = excluded.ciphertext,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')",
+        group_id_str,
+        name,
+        secret.nonce,
+        secret.ciphertext,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// returns false if there was no such secret
+pub async fn delete_secret(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let result = sqlx::query!(
+        "DELETE FROM group_secrets WHERE group_id = ? AND name = ?",
+        group_id_str,
+        name,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list_secrets(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<GroupSecret>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let rows = sqlx::query!(
+        "SELECT name, created_at, updated_at FROM group_secrets WHERE group_id = ? ORDER BY name",
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(GroupSecret {
+                name: r.name,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+                updated_at: decode_timestamp("updated_at", &r.updated_at)?,
+            })
+        })
+        .collect()
+}
+
+/// the encrypted value, only meant to be called from the assembler worker
+pub async fn get_encrypted_secret(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    name: &str,
+) -> Result<Option<EncryptedSecret>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let row = sqlx::query!(
+        "SELECT nonce, ciphertext FROM group_secrets WHERE group_id = ? AND name = ?",
+        group_id_str,
+        name,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| EncryptedSecret {
+        nonce: r.nonce,
+        ciphertext: r.ciphertext,
+    }))
+}
@@ -14,6 +14,15 @@ pub async fn load_instance_settings(db: &SqlitePool) -> Result<InstanceSettings,
         match key {
             "instance_name" => settings.instance_name = value,
             "allow_user_group_creation" => settings.allow_user_group_creation = value == "true",
+            "assembly_max_workers" => {
+                settings.assembly_max_workers =
+                    value.parse().unwrap_or(settings.assembly_max_workers)
+            }
+            "assembly_max_per_group" => {
+                settings.assembly_max_per_group =
+                    value.parse().unwrap_or(settings.assembly_max_per_group)
+            }
+            "webhook_allow_localhost" => settings.webhook_allow_localhost = value == "true",
             _ => {}
         }
     }
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::secrets::EncryptedSecret;
+
+pub struct GroupSigningKeyRow {
+    pub public_key: Vec<u8>,
+    pub seed: EncryptedSecret,
+    pub created_at: DateTime<Utc>,
+}
+
+/// replaces the group's key if it already had one
+pub async fn upsert_group_signing_key(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    public_key: &[u8],
+    seed: &EncryptedSecret,
+) -> Result<(), sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    sqlx::query!(
+        "INSERT INTO group_signing_keys (group_id, public_key, nonce, ciphertext)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT (group_id) DO UPDATE SET
+            public_key = excluded.public_key,
+            nonce = excluded.nonce,
+            ciphertext = excluded.ciphertext,
+            created_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')",
+        group_id_str,
+        public_key,
+        seed.nonce,
+        seed.ciphertext,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_group_signing_key(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Option<GroupSigningKeyRow>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let row = sqlx::query!(
+        "SELECT public_key, nonce, ciphertext, created_at FROM group_signing_keys
+         WHERE group_id = ?",
+        group_id_str,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|r| {
+        Ok(GroupSigningKeyRow {
+            public_key: r.public_key,
+            seed: EncryptedSecret {
+                nonce: r.nonce,
+                ciphertext: r.ciphertext,
+            },
+            created_at: DateTime::parse_from_rfc3339(&r.created_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "created_at".into(),
+                    source: Box::new(e),
+                })?,
+        })
+    })
+    .transpose()
+}
+
+/// returns false if the group had no key of its own
+pub async fn delete_group_signing_key(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let result = sqlx::query!(
+        "DELETE FROM group_signing_keys WHERE group_id = ?",
+        group_id_str,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
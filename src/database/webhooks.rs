@@ -0,0 +1,297 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    database::{decode_timestamp, decode_uuid},
+    models::webhooks::{GroupWebhook, PendingDelivery, WebhookDelivery},
+    secrets::EncryptedSecret,
+};
+
+/// the id is picked by the caller, the secret is encrypted against it
+pub async fn create_webhook(
+    pool: &SqlitePool,
+    id: Uuid,
+    group_id: Uuid,
+    url: &str,
+    secret: &EncryptedSecret,
+) -> Result<GroupWebhook, sqlx::Error> {
+    let id_str = id.to_string();
+    let group_id_str = group_id.to_string();
+
+    let created_at = sqlx::query_scalar!(
+        r#"INSERT INTO group_webhooks (id, group_id, url, secret_nonce, secret_ciphertext)
+           VALUES (?, ?, ?, ?, ?)
+           RETURNING created_at AS "created_at!""#,
+        id_str,
+        group_id_str,
+        url,
+        secret.nonce,
+        secret.ciphertext,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(GroupWebhook {
+        id,
+        url: url.to_string(),
+        created_at: decode_timestamp("created_at", &created_at)?,
+    })
+}
+
+pub async fn list_webhooks(
+    pool: &SqlitePool,
+    group_id: Uuid,
+) -> Result<Vec<GroupWebhook>, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+
+    let rows = sqlx::query!(
+        "SELECT id, url, created_at FROM group_webhooks WHERE group_id = ? ORDER BY id",
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(GroupWebhook {
+                id: decode_uuid("id", &r.id)?,
+                url: r.url,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+            })
+        })
+        .collect()
+}
+
+/// returns false if the group has no such webhook
+pub async fn delete_webhook(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let webhook_id_str = webhook_id.to_string();
+
+    let result = sqlx::query!(
+        "DELETE FROM group_webhooks WHERE id = ? AND group_id = ?",
+        webhook_id_str,
+        group_id_str,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn webhook_exists(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let webhook_id_str = webhook_id.to_string();
+
+    let count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM group_webhooks WHERE id = ? AND group_id = ?",
+        webhook_id_str,
+        group_id_str,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count > 0)
+}
+
+/// queues one delivery of `payload` per webhook of the group, due immediately
+pub async fn enqueue_deliveries(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+    event: &str,
+    payload: &str,
+) -> Result<u64, sqlx::Error> {
+    let group_id_str = group_id.to_string();
+    let assembly_id_str = assembly_id.to_string();
+    let now_millis = Utc::now().timestamp_millis();
+
+    let webhook_ids = sqlx::query_scalar!(
+        "SELECT id FROM group_webhooks WHERE group_id = ?",
+        group_id_str,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    for webhook_id in &webhook_ids {
+        let id = Uuid::now_v7().to_string();
+        sqlx::query!(
+            "INSERT INTO webhook_deliveries (id, webhook_id, assembly_id, event, payload, next_attempt_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            id,
+            webhook_id,
+            assembly_id_str,
+            event,
+            payload,
+            now_millis,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(webhook_ids.len() as u64)
+}
+
+/// takes up to `limit` due deliveries and pushes their next attempt back by `claim_for`, so a
+/// sender that dies mid-request doesn't lose them and two senders don't double up
+pub async fn claim_due_deliveries(
+    pool: &SqlitePool,
+    limit: u32,
+    claim_for: chrono::Duration,
+) -> Result<Vec<PendingDelivery>, sqlx::Error> {
+    let now = Utc::now();
+    let now_millis = now.timestamp_millis();
+    let claimed_until = (now + claim_for).timestamp_millis();
+
+    let rows = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET next_attempt_at = ?
+        WHERE id IN (
+            SELECT id FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= ?
+            ORDER BY next_attempt_at
+            LIMIT ?
+        )
+        RETURNING id AS "id!", webhook_id, event, payload, attempts
+        "#,
+        claimed_until,
+        now_millis,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut deliveries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let webhook = sqlx::query!(
+            "SELECT group_id, url, secret_nonce, secret_ciphertext FROM group_webhooks
+             WHERE id = ?",
+            row.webhook_id,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        // deleted in the meantime, the cascade takes the delivery with it
+        let Some(webhook) = webhook else {
+            continue;
+        };
+
+        deliveries.push(PendingDelivery {
+            id: decode_uuid("id", &row.id)?,
+            group_id: decode_uuid("group_id", &webhook.group_id)?,
+            webhook_id: decode_uuid("webhook_id", &row.webhook_id)?,
+            url: webhook.url,
+            secret: EncryptedSecret {
+                nonce: webhook.secret_nonce,
+                ciphertext: webhook.secret_ciphertext,
+            },
+            event: row.event,
+            payload: row.payload,
+            attempts: row.attempts as u32,
+        });
+    }
+
+    Ok(deliveries)
+}
+
+/// `next_attempt_at` of None means no more attempts, the delivery is settled as `status`
+pub async fn record_delivery_attempt(
+    pool: &SqlitePool,
+    delivery_id: Uuid,
+    status: &str,
+    next_attempt_at: Option<DateTime<Utc>>,
+    response_status: Option<u16>,
+    error: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let delivery_id = delivery_id.to_string();
+    let next_attempt_at = next_attempt_at.unwrap_or(now).timestamp_millis();
+
+    sqlx::query!(
+        "UPDATE webhook_deliveries
+         SET status = ?, attempts = attempts + 1, next_attempt_at = ?, response_status = ?,
+             error = ?, updated_at = ?
+         WHERE id = ?",
+        status,
+        next_attempt_at,
+        response_status,
+        error,
+        now,
+        delivery_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// newest first
+pub async fn list_deliveries(
+    pool: &SqlitePool,
+    webhook_id: Uuid,
+    limit: u32,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let webhook_id_str = webhook_id.to_string();
+
+    let rows = sqlx::query!(
+        "SELECT id, assembly_id, event, status, attempts, next_attempt_at, response_status,
+            error, created_at, updated_at
+         FROM webhook_deliveries
+         WHERE webhook_id = ?
+         ORDER BY id DESC
+         LIMIT ?",
+        webhook_id_str,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(WebhookDelivery {
+                id: decode_uuid("id", &r.id)?,
+                assembly_id: decode_uuid("assembly_id", &r.assembly_id)?,
+                event: r.event,
+                next_attempt_at: match r.status.as_str() {
+                    "pending" => DateTime::from_timestamp_millis(r.next_attempt_at),
+                    _ => None,
+                },
+                status: r.status,
+                attempts: r.attempts as u32,
+                response_status: r.response_status.map(|s| s as u16),
+                error: r.error,
+                created_at: decode_timestamp("created_at", &r.created_at)?,
+                updated_at: decode_timestamp("updated_at", &r.updated_at)?,
+            })
+        })
+        .collect()
+}
+
+/// settled deliveries older than `older_than` are dropped from the log
+pub async fn cleanup_old_deliveries(
+    pool: &SqlitePool,
+    older_than: chrono::Duration,
+) -> Result<u64, sqlx::Error> {
+    // same format as the column default, so the text comparison is chronological
+    let threshold = (Utc::now() - older_than).to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let result = sqlx::query!(
+        "DELETE FROM webhook_deliveries WHERE status != 'pending' AND updated_at < ?",
+        threshold,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
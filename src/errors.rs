@@ -4,7 +4,7 @@ use axum::{
 };
 use tokio::task;
 
-use crate::{assembler::errors::AssemblyError, storage::StorageError};
+use crate::{assembler::errors::AssemblyError, secrets::SecretError, storage::StorageError};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -32,6 +32,8 @@ pub enum Error {
     Internal(String),
     #[error(transparent)]
     Assembly(#[from] AssemblyError),
+    #[error(transparent)]
+    Secret(#[from] SecretError),
 }
 
 pub struct AppError(Error);
@@ -97,23 +99,39 @@ impl IntoResponse for AppError {
                 tracing::error!("internal error: {:?}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
             }
+            Error::Secret(e) => match e {
+                SecretError::NotConfigured => {
+                    return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+                }
+                SecretError::NotFound(_) => {
+                    return (StatusCode::NOT_FOUND, e.to_string()).into_response();
+                }
+                SecretError::Encrypt(_) | SecretError::Decrypt(_) => {
+                    tracing::error!("secret error: {:?}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            },
             Error::Assembly(e) => {
                 let inner = match e {
                     AssemblyError::ArtifactNotFound(msg) => Error::NotFound(msg.clone()),
                     AssemblyError::UnsupportedProvider(msg) => Error::BadRequest(msg.clone()),
                     AssemblyError::ExternalFetch(msg) => Error::Internal(msg.clone()),
-                    AssemblyError::QueueUnavailable => {
-                        Error::Internal("assembly queue unavailable".into())
-                    }
                     AssemblyError::Timeout(_) => Error::Internal("assembly timed out".into()),
+                    AssemblyError::Abandoned => Error::Conflict("assembly abandoned".into()),
                     AssemblyError::Sqlx(e) => Error::Sqlx(e),
                     AssemblyError::Storage(e) => Error::Storage(e),
                     AssemblyError::Zip(e) => Error::Internal(e.to_string()),
                     AssemblyError::Io(e) => Error::Internal(e.to_string()),
                     AssemblyError::Internal(e) => Error::Internal(e.to_string()),
+                    AssemblyError::Secret(e) => Error::Secret(e),
                     AssemblyError::NoArtifacts => {
                         Error::BadRequest("no artifacts provided".to_string())
                     }
+                    AssemblyError::InvalidPlugin(..)
+                    | AssemblyError::ConflictingPlugins(_)
+                    | AssemblyError::Incompatible(_)
+                    | AssemblyError::InvalidConfig(..)
+                    | AssemblyError::InvalidLayout(_) => Error::BadRequest(e.to_string()),
                 };
                 return AppError(inner).into_response();
             }
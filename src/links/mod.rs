@@ -0,0 +1,198 @@
+use std::net::IpAddr;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    assembler,
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::links::{CreatedDownloadLink, DownloadLink, LinkTarget, RedeemedLink},
+    server::AppState,
+    signing,
+};
+
+const DEFAULT_EXPIRY_SECS: u64 = 60 * 60;
+const MAX_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// binds the id, expiry and ip into the url, so none of them can be changed by whoever holds it
+fn signature(key: &[u8], id: Uuid, expires_at: i64, bound_ip: Option<IpAddr>) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    let ip = bound_ip.map(|ip| ip.to_string()).unwrap_or_default();
+    mac.update(format!("{id}\n{expires_at}\n{ip}").as_bytes());
+    mac
+}
+
+/// anyone who may download the target may hand out a link to it
+pub async fn create_link(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    target: LinkTarget,
+    expires_in_secs: Option<u64>,
+    bound_ip: Option<IpAddr>,
+    max_uses: Option<u32>,
+) -> Result<CreatedDownloadLink, Error> {
+    match &target {
+        LinkTarget::Assembly(id) => {
+            assembler::require_assembly_access(state, entity, group_id, *id).await?;
+        }
+        LinkTarget::Artifact(_) => {
+            PermissionChecker::new(&state.db, entity)
+                .require(
+                    PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id),
+                )
+                .await?;
+        }
+    }
+
+    match &target {
+        LinkTarget::Assembly(id) => {
+            if database::assembly::get_assembly(&state.db, group_id, *id)
+                .await?
+                .is_none()
+            {
+                return Err(Error::NotFound(format!("assembly {id} not found")));
+            }
+        }
+        LinkTarget::Artifact(coord) => {
+            database::assembly::get_artifact(&state.db, coord, group_id).await?;
+        }
+    }
+
+    let expires_in_secs = expires_in_secs.unwrap_or(DEFAULT_EXPIRY_SECS);
+    if expires_in_secs == 0 || expires_in_secs > MAX_EXPIRY_SECS {
+        return Err(Error::BadRequest(format!(
+            "expires_in_secs must be between 1 and {MAX_EXPIRY_SECS}"
+        )));
+    }
+    if max_uses == Some(0) {
+        return Err(Error::BadRequest("max_uses must be at least 1".into()));
+    }
+
+    let expires_at = Utc::now().timestamp() + expires_in_secs as i64;
+    let id = database::links::create_link(
+        &state.db,
+        group_id,
+        &target,
+        entity.raw_uuid(),
+        expires_at,
+        bound_ip,
+        max_uses,
+    )
+    .await?;
+
+    let sig = signature(state.link_key.as_slice(), id, expires_at, bound_ip).finalize();
+    let mut url = state
+        .config
+        .public_base_url
+        .join(&format!("/dl/{id}"))
+        .map_err(|e| Error::Internal(e.to_string()))?;
+    url.query_pairs_mut()
+        .append_pair("expires", &expires_at.to_string())
+        .append_pair("sig", &format!("{:x}", sig.into_bytes()));
+
+    tracing::info!(group_id = %group_id, link_id = %id, "download link created");
+
+    let link = database::links::get_link(&state.db, group_id, id)
+        .await?
+        .ok_or_else(|| Error::Internal(format!("download link {id} vanished")))?;
+
+    Ok(CreatedDownloadLink {
+        link,
+        url: url.to_string(),
+    })
+}
+
+pub async fn list_links(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<DownloadLink>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::links::list_links(&state.db, group_id).await?)
+}
+
+pub async fn revoke_link(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::List).in_group(group_id))
+        .await?;
+
+    let link = database::links::get_link(&state.db, group_id, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("download link {id} not found")))?;
+
+    // whoever created a link may take it back, anyone else needs delete
+    if link.created_by != entity.raw_uuid() {
+        PermissionChecker::new(&state.db, entity)
+            .require(
+                PermissionCheck::new(ResourceType::Artifact, Action::Delete).in_group(group_id),
+            )
+            .await?;
+    }
+
+    if !database::links::revoke_link(&state.db, group_id, id).await? {
+        return Err(Error::Conflict("download link already revoked".into()));
+    }
+
+    tracing::info!(group_id = %group_id, link_id = %id, "download link revoked");
+
+    Ok(())
+}
+
+/// checks the signature, binding and remaining uses. the use itself is counted by
+/// `record_use` once the download is served.
+pub async fn redeem(
+    state: &AppState,
+    id: Uuid,
+    expires_at: i64,
+    sig: &str,
+    client_ip: IpAddr,
+) -> Result<RedeemedLink, Error> {
+    let (stored_expires_at, bound_ip) = database::links::get_link_binding(&state.db, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("download link {id} not found")))?;
+
+    let sig = signing::decode_hex(sig).unwrap_or_default();
+
+    if stored_expires_at != expires_at
+        || signature(state.link_key.as_slice(), id, expires_at, bound_ip)
+            .verify_slice(&sig)
+            .is_err()
+    {
+        return Err(Error::NotAllowed("invalid download link signature".into()));
+    }
+
+    if bound_ip.is_some_and(|ip| ip != client_ip) {
+        return Err(Error::NotAllowed(
+            "download link is bound to another address".into(),
+        ));
+    }
+
+    database::links::get_usable_link(&state.db, id, Utc::now().timestamp())
+        .await?
+        .ok_or_else(|| Error::NotAllowed("download link expired, revoked or used up".into()))
+}
+
+pub async fn record_use(state: &AppState, id: Uuid) -> Result<(), Error> {
+    // a concurrent download may have taken the last use since `redeem`, this one is let through
+    if !database::links::consume_link(&state.db, id).await? {
+        tracing::warn!(link_id = %id, "download link used up while serving");
+    }
+
+    Ok(())
+}
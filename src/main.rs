@@ -1,15 +1,20 @@
 mod assembler;
 mod auth;
+mod configs;
 mod database;
 mod errors;
+mod links;
 mod middleware;
 mod models;
 mod plugins;
 mod routes;
+mod secrets;
 mod server;
+mod signing;
 mod storage;
+mod webhooks;
 
-use crate::server::Server;
+use crate::{secrets::SecretsKey, server::Server};
 use std::{
     net::{IpAddr, SocketAddr},
     path::PathBuf,
@@ -29,6 +34,10 @@ pub struct Config {
     pub public_base_url: Url,
     pub log_ips: bool,
     pub trusted_proxy: Option<IpAddr>,
+    // None = group secrets are disabled
+    pub secrets_key: Option<SecretsKey>,
+    // None = use the instance setting, 0 = leave assemblies to standalone workers
+    pub assembly_workers: Option<usize>,
 }
 
 impl Config {
@@ -76,6 +85,25 @@ impl Config {
             ),
         };
 
+        let secrets_key = match std::env::var("COGERE_SECRETS_KEY") {
+            Ok(k) => Some(
+                SecretsKey::from_base64(&k)
+                    .map_err(|e| format!("invalid COGERE_SECRETS_KEY: {e}"))?,
+            ),
+            Err(_) => {
+                eprintln!("Warning: COGERE_SECRETS_KEY not set, group secrets are disabled");
+                None
+            }
+        };
+
+        let assembly_workers = match std::env::var("COGERE_ASSEMBLY_WORKERS") {
+            Ok(n) => Some(
+                n.parse::<usize>()
+                    .map_err(|e| format!("invalid COGERE_ASSEMBLY_WORKERS: {e}"))?,
+            ),
+            Err(_) => None,
+        };
+
         Ok(Self {
             data_folder,
             socket_addr,
@@ -83,6 +111,8 @@ impl Config {
             public_base_url,
             log_ips,
             trusted_proxy,
+            secrets_key,
+            assembly_workers,
         })
     }
 }
@@ -93,6 +123,11 @@ impl std::fmt::Debug for Config {
             .field("data_folder", &self.data_folder)
             .field("socket_addr", &self.socket_addr)
             .field("cookie_key", &"[redacted]")
+            .field(
+                "secrets_key",
+                &self.secrets_key.as_ref().map(|_| "[redacted]"),
+            )
+            .field("assembly_workers", &self.assembly_workers)
             .finish()
     }
 }
@@ -120,5 +155,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     });
 
-    Server::new(config).await?.serve().await
+    match std::env::args().nth(1).as_deref() {
+        None | Some("serve") => Server::new(config).await?.serve().await,
+        Some("worker") => Server::new(config).await?.work().await,
+        Some(other) => {
+            eprintln!("Unknown command '{other}', expected 'serve' or 'worker'");
+            std::process::exit(1);
+        }
+    }
 }
@@ -1,9 +1,14 @@
+use std::{collections::BTreeMap, fmt};
+
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    assembler::{ArtifactCoordinate, errors::AssemblyError},
+    assembler::{
+        ArtifactCoordinate, AssemblyOptions, archive::ArchiveFormat, errors::AssemblyError,
+        layout::Layout,
+    },
     models::plugins::{Plugin, PluginVersion},
 };
 
@@ -18,6 +23,7 @@ pub enum AssemblyStatus {
     Failed {
         error: AssemblyError,
     },
+    Cancelled,
 }
 
 #[derive(Debug, Clone)]
@@ -38,4 +44,124 @@ pub struct Assembly {
     pub error: Option<String>,
     pub blob_id: Option<Uuid>,
     pub artifacts: Vec<ArtifactCoordinate>,
+    pub minecraft_version: Option<String>,
+    pub java_version: Option<u16>,
+    pub configs: Vec<AssemblyConfig>,
+    pub variables: BTreeMap<String, String>,
+    pub layout: Layout,
+    pub filename: Option<String>,
+    pub format: ArchiveFormat,
+    // only set while pending
+    pub queue_position: Option<u64>,
+    pub progress: Option<AssemblyProgress>,
+    // name it's pinned under, pinned assemblies never expire
+    pub release: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssemblyRelease {
+    pub name: String,
+    pub assembly_id: Uuid,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// a saved assemble request that can be rebuilt by name
+#[derive(Debug, Serialize)]
+pub struct AssemblyProfile {
+    pub id: Uuid,
+    pub name: String,
+    pub artifacts: Vec<ArtifactCoordinate>,
+    #[serde(flatten)]
+    pub options: AssemblyOptions,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// an assembly a profile produced
+#[derive(Debug, Serialize)]
+pub struct ProfileRun {
+    pub assembly_id: Uuid,
+    pub requested_by: Uuid,
+    /// an identical completed assembly was handed out
+    pub reused: bool,
+    // None = the assembly has been cleaned up since
+    pub status: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ProgressStage {
+    Resolving,
+    Fetching { artifact: usize, total: usize },
+    Compressing,
+    Storing,
+}
+
+impl fmt::Display for ProgressStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgressStage::Resolving => write!(f, "resolving"),
+            ProgressStage::Fetching { artifact, total } => {
+                write!(f, "fetching artifact {artifact} of {total}")
+            }
+            ProgressStage::Compressing => write!(f, "compressing"),
+            ProgressStage::Storing => write!(f, "storing"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssemblyProgress {
+    #[serde(flatten)]
+    pub stage: ProgressStage,
+    pub artifacts: Vec<ArtifactTiming>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactTiming {
+    /// `group_id:artifact_id:version`
+    pub artifact: String,
+    /// looking the version up, plus the download for jars that aren't stored
+    #[serde(alias = "fetch_ms")]
+    pub resolve_ms: u64,
+    /// reading the jar and copying it into the archive, set once compressing is done. stored
+    /// jars are only read here, so their storage time counts towards this
+    pub write_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuiltArtifact {
+    #[serde(flatten)]
+    pub coordinate: ArtifactCoordinate,
+    pub sha256: Option<String>,
+    pub size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedArtifact {
+    pub old: BuiltArtifact,
+    pub new: BuiltArtifact,
+    /// the new version sorts before the old one
+    pub downgrade: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssemblyDiff {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub added: Vec<BuiltArtifact>,
+    pub removed: Vec<BuiltArtifact>,
+    /// same plugin, different version
+    pub upgraded: Vec<ChangedArtifact>,
+    /// same version, but a different jar went in
+    pub rebuilt: Vec<ChangedArtifact>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssemblyConfig {
+    pub path: String,
+    pub revision: u32,
 }
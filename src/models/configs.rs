@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigTemplate {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub path: String,
+    pub latest_revision: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigTemplateVersion {
+    pub id: Uuid,
+    pub template_id: Uuid,
+    pub path: String,
+    pub revision: u32,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
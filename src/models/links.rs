@@ -0,0 +1,45 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::assembler::ArtifactCoordinate;
+
+#[derive(Debug, Clone)]
+pub enum LinkTarget {
+    Assembly(Uuid),
+    Artifact(ArtifactCoordinate),
+}
+
+#[derive(Debug, Serialize)]
+pub struct DownloadLink {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub assembly_id: Option<Uuid>,
+    /// `group_id:artifact_id:version`
+    pub artifact: Option<String>,
+    pub created_by: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub bound_ip: Option<IpAddr>,
+    // None = unlimited until it expires
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// the url can only be shown once, it isn't stored
+#[derive(Debug, Serialize)]
+pub struct CreatedDownloadLink {
+    #[serde(flatten)]
+    pub link: DownloadLink,
+    pub url: String,
+}
+
+/// what a redeemed link gives access to
+#[derive(Debug)]
+pub struct RedeemedLink {
+    pub group_id: Uuid,
+    pub target: LinkTarget,
+}
@@ -1,6 +1,11 @@
 pub mod assembly;
 pub mod auth;
 pub mod blobs;
+pub mod configs;
 pub mod groups;
+pub mod links;
 pub mod plugins;
+pub mod secrets;
 pub mod settings;
+pub mod signing;
+pub mod webhooks;
@@ -1,4 +1,7 @@
 use core::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,7 +27,8 @@ impl fmt::Display for PluginSource {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Visibility {
     Public,
     Private,
@@ -60,6 +64,11 @@ pub struct PluginVersion {
     pub version: String,
     // None = not in cache yet
     pub blob_id: Option<Uuid>,
+    pub api_version: Option<String>,
+    // empty = no declared restriction
+    pub game_versions: Vec<String>,
+    // highest class file target, None = unknown
+    pub min_java_version: Option<u16>,
 }
 
 impl PluginVersion {
@@ -105,3 +114,28 @@ pub struct GroupPluginSummary {
     pub latest_version: Option<String>,
     pub is_cached: bool,
 }
+
+/// the group a plugin was uploaded to, other groups can only attach it while it's public
+pub struct PluginOwner {
+    pub group_id: Uuid,
+    pub visibility: Visibility,
+}
+
+/// a public plugin owned by another group
+#[derive(Debug, Serialize)]
+pub struct SharedPlugin {
+    pub id: Uuid,
+    pub plugin_group_id: String,
+    pub plugin_artifact_id: String,
+    pub owner_group_id: Uuid,
+    pub owner_group_name: String,
+    pub attached: bool,
+}
+
+/// a group a published plugin is attached to
+#[derive(Debug, Serialize)]
+pub struct PluginShare {
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub attached_at: DateTime<Utc>,
+}
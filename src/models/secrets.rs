@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// only metadata, the value never leaves the database outside the assembler worker
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupSecret {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
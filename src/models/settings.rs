@@ -6,6 +6,11 @@ pub struct InstanceSettings {
     pub assembly_expiry_secs: u64,
     pub assembly_cleanup_interval_secs: u64,
     pub assembly_max_age_days: u64,
+    pub assembly_lease_secs: u64,
+    pub assembly_max_attempts: u32,
+    pub assembly_max_workers: usize,
+    pub assembly_max_per_group: u32,
+    pub webhook_allow_localhost: bool,
 }
 
 impl Default for InstanceSettings {
@@ -18,6 +23,11 @@ impl Default for InstanceSettings {
             assembly_expiry_secs: 60 * 30,      // 30 minutes
             assembly_cleanup_interval_secs: 60, // minute
             assembly_max_age_days: 30,          // 30 days
+            assembly_lease_secs: 60,            // minute
+            assembly_max_attempts: 3,
+            assembly_max_workers: 4,
+            assembly_max_per_group: 2,
+            webhook_allow_localhost: false,
         }
     }
 }
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct PublicSigningKey {
+    pub key_id: String,
+    pub algorithm: &'static str,
+    /// raw 32 byte key, base64
+    pub public_key: String,
+    /// spki pem, for `openssl pkeyutl -verify`
+    pub pem: String,
+    /// None for the instance key
+    pub group_id: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetachedSignature {
+    pub key_id: String,
+    pub algorithm: &'static str,
+    /// over the raw sha256 digest of the download, base64
+    pub signature: String,
+    pub sha256: String,
+}
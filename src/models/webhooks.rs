@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::secrets::EncryptedSecret;
+
+/// the signing secret is left out, it's only returned once by `CreatedWebhook`
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupWebhook {
+    pub id: Uuid,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: GroupWebhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub assembly_id: Uuid,
+    pub event: String,
+    pub status: String,
+    pub attempts: u32,
+    // only set while pending
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// a claimed delivery with everything needed to send it
+pub struct PendingDelivery {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub webhook_id: Uuid,
+    pub url: String,
+    /// decrypted only to sign the delivery
+    pub secret: EncryptedSecret,
+    pub event: String,
+    pub payload: String,
+    pub attempts: u32,
+}
@@ -0,0 +1,88 @@
+use std::io::{Read, Seek};
+
+/// class file major version 45 is java 1.1, every release since bumps it by one
+const MAJOR_VERSION_OFFSET: u16 = 44;
+const CLASS_MAGIC: [u8; 4] = [0xCA, 0xFE, 0xBA, 0xBE];
+/// multi-release jars keep newer classes here, the jvm only loads them on matching runtimes
+const VERSIONED_PREFIX: &str = "META-INF/versions/";
+
+/// scans every class file in a jar and returns the lowest java version able to load all of them.
+/// returns None if the jar contains no class files.
+pub fn min_java_version<R: Read + Seek>(jar: R) -> Result<Option<u16>, zip::result::ZipError> {
+    let mut archive = zip::ZipArchive::new(jar)?;
+    let mut highest_major: Option<u16> = None;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file()
+            || !file.name().ends_with(".class")
+            || file.name().starts_with(VERSIONED_PREFIX)
+        {
+            continue;
+        }
+
+        let mut header = [0u8; 8];
+        if file.read_exact(&mut header).is_err() || header[..4] != CLASS_MAGIC {
+            continue;
+        }
+
+        let major = u16::from_be_bytes([header[6], header[7]]);
+        highest_major = highest_major.max(Some(major));
+    }
+
+    Ok(highest_major.map(|major| major.saturating_sub(MAJOR_VERSION_OFFSET)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    fn class(major: u16) -> Vec<u8> {
+        let mut bytes = CLASS_MAGIC.to_vec();
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&major.to_be_bytes());
+        bytes
+    }
+
+    fn jar(entries: &[(&str, Vec<u8>)]) -> Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, zip::write::FileOptions::<()>::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        let mut jar = writer.finish().unwrap();
+        jar.set_position(0);
+        jar
+    }
+
+    #[test]
+    fn highest_class_version_wins() {
+        let jar = jar(&[("a/A.class", class(52)), ("a/B.class", class(61))]);
+        assert_eq!(min_java_version(jar).unwrap(), Some(17));
+    }
+
+    #[test]
+    fn versioned_classes_are_ignored() {
+        let jar = jar(&[
+            ("a/A.class", class(52)),
+            ("META-INF/versions/21/a/A.class", class(65)),
+        ]);
+        assert_eq!(min_java_version(jar).unwrap(), Some(8));
+    }
+
+    #[test]
+    fn jar_without_classes() {
+        let jar = jar(&[("plugin.yml", b"name: Example\n".to_vec())]);
+        assert_eq!(min_java_version(jar).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_class_files_are_skipped() {
+        let jar = jar(&[("a/A.class", class(55)), ("a/B.class", b"nope".to_vec())]);
+        assert_eq!(min_java_version(jar).unwrap(), Some(11));
+    }
+}
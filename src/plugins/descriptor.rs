@@ -0,0 +1,94 @@
+use std::io::{Read, Seek};
+
+use serde::Deserialize;
+
+/// descriptor files paper looks at, in order of preference
+const DESCRIPTOR_FILES: [&str; 2] = ["paper-plugin.yml", "plugin.yml"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum DescriptorError {
+    #[error("invalid jar: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("invalid plugin descriptor: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginDescriptor {
+    pub name: String,
+    pub main: String,
+    // filled from the raw descriptor, yaml turns `api-version: 1.20` into 1.2
+    #[serde(skip)]
+    pub api_version: Option<String>,
+}
+
+/// reads the plugin descriptor from a jar, returns None for jars that are not bukkit/paper plugins.
+pub fn read_descriptor<R: Read + Seek>(
+    jar: R,
+) -> Result<Option<PluginDescriptor>, DescriptorError> {
+    let mut archive = zip::ZipArchive::new(jar)?;
+
+    for name in DESCRIPTOR_FILES {
+        let mut file = match archive.by_name(name) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut descriptor: PluginDescriptor = serde_yaml::from_str(&contents)?;
+        descriptor.api_version = raw_api_version(&contents);
+
+        return Ok(Some(descriptor));
+    }
+
+    Ok(None)
+}
+
+fn raw_api_version(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("api-version:"))
+        .map(|value| value.split(" #").next().unwrap_or(value))
+        .map(|value| {
+            value
+                .trim()
+                .trim_matches(|c| c == '\'' || c == '"')
+                .to_string()
+        })
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_version_keeps_trailing_zero() {
+        let contents = "name: Example\nmain: com.example.Example\napi-version: 1.20\n";
+        assert_eq!(raw_api_version(contents).as_deref(), Some("1.20"));
+        assert_eq!(raw_api_version("api-version: 1.2").as_deref(), Some("1.2"));
+    }
+
+    #[test]
+    fn api_version_strips_quotes_and_comments() {
+        assert_eq!(
+            raw_api_version("api-version: '1.20'").as_deref(),
+            Some("1.20")
+        );
+        assert_eq!(
+            raw_api_version("api-version: \"1.21\" # paper").as_deref(),
+            Some("1.21")
+        );
+    }
+
+    #[test]
+    fn missing_api_version() {
+        assert_eq!(raw_api_version("name: Example\n"), None);
+        assert_eq!(raw_api_version("api-version:\n"), None);
+    }
+}
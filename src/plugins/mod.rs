@@ -13,11 +13,16 @@ use crate::{
     storage::LocalStorage,
 };
 
+pub mod bytecode;
+pub mod descriptor;
+pub mod sharing;
+
 pub struct UploadPluginOptions {
     pub group_id: Uuid,
     pub plugin_group_id: String,
     pub plugin_artifact_id: String,
     pub version: String,
+    pub game_versions: Vec<String>,
     pub file: Bytes,
 }
 
@@ -56,6 +61,19 @@ pub async fn upload_plugin(
     if input.file.is_empty() {
         return Err(Error::BadRequest("uploaded file is empty".into()));
     }
+
+    // metadata is best effort, libraries and mods are stored without it
+    let descriptor =
+        descriptor::read_descriptor(std::io::Cursor::new(&input.file)).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "could not read plugin descriptor, storing without it");
+            None
+        });
+    let min_java_version = bytecode::min_java_version(std::io::Cursor::new(&input.file))
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "could not read class files, storing without java version");
+            None
+        });
+
     let sha256 = {
         let mut hasher = Sha256::new();
         hasher.update(&input.file);
@@ -105,6 +123,9 @@ pub async fn upload_plugin(
             sha256: sha256.clone(),
             size_bytes,
             is_new_blob,
+            api_version: descriptor.and_then(|d| d.api_version),
+            game_versions: input.game_versions,
+            min_java_version,
         },
     )
     .await;
@@ -0,0 +1,191 @@
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::plugins::{PluginOwner, PluginShare, SharedPlugin, Visibility},
+    server::AppState,
+};
+
+/// the owner of a plugin that is owned by `group_id`
+async fn owned_plugin(
+    state: &AppState,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<PluginOwner, Error> {
+    database::plugins::get_plugin_owner(&state.db, plugin_id)
+        .await?
+        .filter(|owner| owner.group_id == group_id)
+        .ok_or_else(|| Error::NotFound(format!("group doesn't own plugin {plugin_id}")))
+}
+
+async fn require_manage(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(
+            PermissionCheck::new(ResourceType::Plugin, Action::Manage)
+                .in_group(group_id)
+                .with_resource_id(plugin_id),
+        )
+        .await
+}
+
+/// lets other groups attach the plugin
+pub async fn publish_plugin(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<(), Error> {
+    require_manage(state, entity, group_id, plugin_id).await?;
+    owned_plugin(state, group_id, plugin_id).await?;
+
+    database::plugins::set_plugin_visibility(&state.db, plugin_id, Visibility::Public).await?;
+
+    tracing::info!(group_id = %group_id, plugin_id = %plugin_id, "plugin published");
+
+    Ok(())
+}
+
+/// makes the plugin private again, every group that attached it loses access
+pub async fn unpublish_plugin(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<u64, Error> {
+    require_manage(state, entity, group_id, plugin_id).await?;
+    owned_plugin(state, group_id, plugin_id).await?;
+
+    let detached =
+        database::plugins::set_plugin_visibility(&state.db, plugin_id, Visibility::Private).await?;
+
+    tracing::info!(group_id = %group_id, plugin_id = %plugin_id, detached, "plugin unpublished");
+
+    Ok(detached)
+}
+
+pub async fn list_plugin_shares(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<Vec<PluginShare>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(
+            PermissionCheck::new(ResourceType::Plugin, Action::Get)
+                .in_group(group_id)
+                .with_resource_id(plugin_id),
+        )
+        .await?;
+    owned_plugin(state, group_id, plugin_id).await?;
+
+    Ok(database::plugins::list_plugin_shares(&state.db, plugin_id).await?)
+}
+
+/// takes the plugin away from a single group, it stays public for the others
+pub async fn revoke_plugin_share(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+    target_group_id: Uuid,
+) -> Result<(), Error> {
+    require_manage(state, entity, group_id, plugin_id).await?;
+    owned_plugin(state, group_id, plugin_id).await?;
+
+    if !database::plugins::detach_plugin(&state.db, target_group_id, plugin_id).await? {
+        return Err(Error::NotFound(format!(
+            "plugin {plugin_id} isn't attached to group {target_group_id}"
+        )));
+    }
+
+    tracing::info!(group_id = %group_id, plugin_id = %plugin_id, target_group_id = %target_group_id, "plugin share revoked");
+
+    Ok(())
+}
+
+/// public plugins of other groups that could be attached
+pub async fn list_shared_plugins(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<SharedPlugin>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Plugin, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::plugins::list_shared_plugins(&state.db, group_id).await?)
+}
+
+/// makes another group's public plugin resolvable in this group's assemblies
+pub async fn attach_plugin(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Plugin, Action::Create).in_group(group_id))
+        .await?;
+
+    // private plugins of other groups don't exist as far as this group is concerned
+    let owner = database::plugins::get_plugin_owner(&state.db, plugin_id)
+        .await?
+        .filter(|owner| owner.group_id == group_id || owner.visibility == Visibility::Public)
+        .ok_or_else(|| Error::NotFound(format!("plugin {plugin_id} not found")))?;
+
+    if owner.group_id == group_id {
+        return Err(Error::BadRequest(format!(
+            "group already owns plugin {plugin_id}"
+        )));
+    }
+
+    // coordinates have to stay unambiguous within a group
+    if let Some(name) =
+        database::plugins::find_plugin_name_clash(&state.db, group_id, plugin_id).await?
+    {
+        return Err(Error::Conflict(format!(
+            "group already has a plugin {name}"
+        )));
+    }
+
+    if !database::plugins::attach_plugin(&state.db, group_id, plugin_id).await? {
+        return Err(Error::Conflict(format!(
+            "plugin {plugin_id} is no longer public"
+        )));
+    }
+
+    tracing::info!(group_id = %group_id, plugin_id = %plugin_id, owner_group_id = %owner.group_id, "shared plugin attached");
+
+    Ok(())
+}
+
+pub async fn detach_plugin(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    plugin_id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Plugin, Action::Delete).in_group(group_id))
+        .await?;
+
+    if !database::plugins::detach_plugin(&state.db, group_id, plugin_id).await? {
+        return Err(Error::NotFound(format!(
+            "shared plugin {plugin_id} isn't attached"
+        )));
+    }
+
+    tracing::info!(group_id = %group_id, plugin_id = %plugin_id, "shared plugin detached");
+
+    Ok(())
+}
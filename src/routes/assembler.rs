@@ -1,24 +1,40 @@
 use crate::{
-    assembler::{self, ArtifactCoordinate},
+    assembler::{
+        self, ArtifactCoordinate, AssemblyOptions, archive::ArchiveFormat, profiles, releases,
+    },
     auth::{
         extractor::AuthenticatedEntity,
         permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
     },
     database,
     errors::{AppError, Error},
-    models::assembly::AssemblyStatus,
+    models::{
+        assembly::{AssemblyProfile, AssemblyStatus, ProfileRun},
+        plugins::Blob,
+    },
+    routes::download,
     server::AppState,
-    storage::{LocalStorage, StorageError},
+    signing,
 };
 use axum::{
     Json,
-    extract::{Path, State},
-    http::{StatusCode, header},
-    response::{IntoResponse, Response},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
+use futures_util::{Stream, StreamExt, stream};
 use serde::{Deserialize, de};
+use std::{convert::Infallible, time::Duration};
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
+/// how often an event stream checks its assembly without being woken
+const EVENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 impl<'de> de::Deserialize<'de> for ArtifactCoordinate {
     fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let s = String::deserialize(deserializer)?;
@@ -37,6 +53,8 @@ impl<'de> de::Deserialize<'de> for ArtifactCoordinate {
 #[derive(Clone, Deserialize)]
 pub struct RequestAssembly {
     pub artifacts: Vec<ArtifactCoordinate>,
+    #[serde(flatten)]
+    pub options: AssemblyOptions,
 }
 
 pub async fn request_assembly(
@@ -45,24 +63,100 @@ pub async fn request_assembly(
     Path(group_id): Path<Uuid>,
     Json(request): Json<RequestAssembly>,
 ) -> Result<Response, AppError> {
-    match assembler::request_assembly(&state, &entity, group_id, request.artifacts).await {
-        Ok(data) => Ok((StatusCode::CREATED, Json(data)).into_response()),
-        Err(e) => Err(e.into()),
+    let response = assembler::request_assembly(
+        &state,
+        &entity,
+        group_id,
+        request.artifacts,
+        request.options,
+    )
+    .await?;
+
+    Ok(assembly_requested(response))
+}
+
+fn assembly_requested(response: assembler::RequestAssemblyResponse) -> Response {
+    if response.reused {
+        (StatusCode::OK, Json(response)).into_response()
+    } else {
+        (StatusCode::CREATED, Json(response)).into_response()
     }
 }
 
+pub async fn list_profiles(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<Vec<AssemblyProfile>>, AppError> {
+    Ok(Json(
+        profiles::list_profiles(&state, &entity, group_id).await?,
+    ))
+}
+
+pub async fn get_profile(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Json<AssemblyProfile>, AppError> {
+    Ok(Json(
+        profiles::get_profile(&state, &entity, group_id, &name).await?,
+    ))
+}
+
+/// takes the same body as an assemble request
+pub async fn save_profile(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+    Json(request): Json<RequestAssembly>,
+) -> Result<Json<AssemblyProfile>, AppError> {
+    Ok(Json(
+        profiles::save_profile(
+            &state,
+            &entity,
+            group_id,
+            &name,
+            request.artifacts,
+            request.options,
+        )
+        .await?,
+    ))
+}
+
+pub async fn delete_profile(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Response, AppError> {
+    profiles::delete_profile(&state, &entity, group_id, &name).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn assemble_profile(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Response, AppError> {
+    let response = profiles::assemble_profile(&state, &entity, group_id, &name).await?;
+    Ok(assembly_requested(response))
+}
+
+pub async fn list_profile_runs(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Json<Vec<ProfileRun>>, AppError> {
+    Ok(Json(
+        profiles::list_profile_runs(&state, &entity, group_id, &name).await?,
+    ))
+}
+
 pub async fn get_assembly(
     State(state): State<AppState>,
     entity: AuthenticatedEntity,
     Path((group_id, id)): Path<(Uuid, Uuid)>,
 ) -> Result<Response, AppError> {
-    PermissionChecker::new(&state.db, &entity)
-        .require(
-            PermissionCheck::new(ResourceType::Artifact, Action::Get)
-                .in_group(group_id)
-                .with_resource_id(id),
-        )
-        .await?;
+    assembler::require_assembly_access(&state, &entity, group_id, id).await?;
 
     match database::assembly::get_assembly(&state.db, group_id, id).await {
         Ok(Some(assembly)) => Ok(Json(assembly).into_response()),
@@ -71,20 +165,129 @@ pub async fn get_assembly(
     }
 }
 
-pub async fn download_assembly(
+#[derive(Deserialize)]
+pub struct DiffQuery {
+    pub from: Uuid,
+    pub to: Uuid,
+}
+
+pub async fn diff_assemblies(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Response, AppError> {
+    let diff =
+        assembler::diff::diff_assemblies(&state, &entity, group_id, query.from, query.to).await?;
+
+    Ok(Json(diff).into_response())
+}
+
+/// server-sent events with the full assembly whenever it changes. the stream ends after the
+/// final state, so a deploy script can simply read until it closes.
+pub async fn assembly_events(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    assembler::require_assembly_access(&state, &entity, group_id, id).await?;
+
+    if database::assembly::get_assembly(&state.db, group_id, id)
+        .await?
+        .is_none()
+    {
+        return Err(Error::NotFound(format!("assembly {id} not found")).into());
+    }
+
+    // subscribed before the first read, so no change slips in between
+    let watcher = state.assembly_watchers.subscribe(id);
+
+    // the state is the last sent payload, None once the final state went out
+    let events = stream::unfold(
+        (Some(String::new()), watcher),
+        move |(last, mut watcher)| {
+            let db = state.db.clone();
+            async move {
+                let last = last?;
+                loop {
+                    let assembly = match database::assembly::get_assembly(&db, group_id, id).await {
+                        Ok(Some(assembly)) => assembly,
+                        Ok(None) => return None,
+                        Err(e) => {
+                            tracing::warn!(assembly_id = %id, error = %e, "assembly event stream failed");
+                            return None;
+                        }
+                    };
+
+                    let finished = !matches!(assembly.status.as_str(), "pending" | "running");
+                    let data = serde_json::to_string(&assembly).ok()?;
+
+                    if data != last {
+                        let event = Event::default().event("assembly").data(data.clone());
+                        return Some((Ok(event), ((!finished).then_some(data), watcher)));
+                    }
+
+                    // woken by builds in this process, polled for builds in standalone workers
+                    tokio::select! {
+                        changed = watcher.changed() => {
+                            if changed.is_err() {
+                                tokio::time::sleep(EVENT_POLL_INTERVAL).await;
+                            }
+                        }
+                        _ = tokio::time::sleep(EVENT_POLL_INTERVAL) => {}
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+pub async fn cancel_assembly(
     State(state): State<AppState>,
     entity: AuthenticatedEntity,
     Path((group_id, id)): Path<(Uuid, Uuid)>,
 ) -> Result<Response, AppError> {
+    // stopping someone else's build is as much a write as starting one
     PermissionChecker::new(&state.db, &entity)
-        .require(
-            PermissionCheck::new(ResourceType::Artifact, Action::Get)
-                .in_group(group_id)
-                .with_resource_id(id),
-        )
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Create).in_group(group_id))
         .await?;
 
-    let status = database::assembly::get_assembly_status(&state.db, id)
+    if !database::assembly::cancel_assembly(&state.db, group_id, id).await? {
+        return match database::assembly::get_assembly(&state.db, group_id, id).await? {
+            Some(_) => Err(Error::Conflict("assembly already finished".into()).into()),
+            None => Err(Error::NotFound(format!("assembly {id} not found")).into()),
+        };
+    }
+
+    tracing::info!(group_id = %group_id, assembly_id = %id, "assembly cancelled");
+    state.assembly_watchers.notify(id);
+
+    match database::assembly::get_assembly(&state.db, group_id, id).await? {
+        Some(assembly) => Ok(Json(assembly).into_response()),
+        None => Err(Error::NotFound(format!("assembly {id} not found")).into()),
+    }
+}
+
+pub async fn download_assembly(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    headers: HeaderMap,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    assembler::require_assembly_access(&state, &entity, group_id, id).await?;
+
+    serve_assembly(&state, &headers, group_id, id, &format!("assembly-{id}")).await
+}
+
+/// the completed archive's blob and its format
+async fn assembly_blob(
+    state: &AppState,
+    group_id: Uuid,
+    id: Uuid,
+) -> Result<(ArchiveFormat, Blob), AppError> {
+    let status = database::assembly::get_assembly_status(&state.db, group_id, id)
         .await?
         .ok_or_else(|| Error::NotFound(format!("assembly {id} not found")))?;
 
@@ -93,25 +296,163 @@ pub async fn download_assembly(
         AssemblyStatus::Failed { .. } => {
             return Err(Error::BadRequest("assembly failed".into()).into());
         }
+        AssemblyStatus::Cancelled => {
+            return Err(Error::BadRequest("assembly was cancelled".into()).into());
+        }
         AssemblyStatus::Pending | AssemblyStatus::Running => {
             return Err(Error::BadRequest("assembly not ready yet".into()).into());
         }
     };
 
-    let data = state.storage.get(blob_id).await.map_err(|e| match e {
-        StorageError::NotFound(_) => Error::NotFound(format!("blob {blob_id} not found")),
-        e => Error::Storage(e),
-    })?;
+    let format = database::assembly::get_assembly_format(&state.db, group_id, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("assembly {id} not found")))?;
+
+    let blob = database::blobs::get_blob(&state.db, blob_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("blob {blob_id} not found")))?;
+
+    Ok((format, blob))
+}
+
+pub async fn serve_assembly(
+    state: &AppState,
+    headers: &HeaderMap,
+    group_id: Uuid,
+    id: Uuid,
+    filename: &str,
+) -> Result<Response, AppError> {
+    let (format, blob) = assembly_blob(state, group_id, id).await?;
+    let signature = signing::sign(state, group_id, &blob.sha256).await?;
+
+    download::serve_blob(
+        state,
+        headers,
+        &blob,
+        format.content_type(),
+        &format!("{filename}.{}", format.extension()),
+        &signature,
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct DeltaRequest {
+    /// sha256 of every jar the server has installed
+    pub installed: Vec<String>,
+}
+
+pub async fn download_delta(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<DeltaRequest>,
+) -> Result<Response, AppError> {
+    let delta =
+        assembler::delta::build_delta(&state, &entity, group_id, id, request.installed).await?;
+    let signature = signing::sign(&state, group_id, &delta.sha256).await?;
+
+    let file = tokio::fs::File::from_std(
+        delta
+            .file
+            .file
+            .try_clone()
+            .map_err(|e| Error::Internal(e.to_string()))?,
+    );
+    // the temp file goes with the stream, so it's removed once the body is sent or dropped
+    let temp = delta.file;
+    let body = Body::from_stream(ReaderStream::new(file).map(move |chunk| {
+        let _temp = &temp;
+        chunk
+    }));
 
     Ok((
         [
-            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_TYPE, ArchiveFormat::Zip.content_type()),
             (
                 header::CONTENT_DISPOSITION,
-                &format!("attachment; filename=\"assembly-{id}.zip\""),
+                &format!("attachment; filename=\"assembly-{id}-delta.zip\""),
             ),
+            (header::CONTENT_LENGTH, &delta.size_bytes.to_string()),
+            (signing::SIGNATURE_HEADER, &signature.signature),
+            (signing::KEY_ID_HEADER, &signature.key_id),
         ],
-        data,
+        body,
     )
         .into_response())
 }
+
+/// the same signature the download carries in its headers, for clients that want a `.sig` file
+pub async fn assembly_signature(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    assembler::require_assembly_access(&state, &entity, group_id, id).await?;
+
+    let (_, blob) = assembly_blob(&state, group_id, id).await?;
+    let signature = signing::sign(&state, group_id, &blob.sha256).await?;
+
+    Ok(Json(signature).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct PinRelease {
+    pub assembly_id: Uuid,
+}
+
+pub async fn pin_release(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+    Json(request): Json<PinRelease>,
+) -> Result<Response, AppError> {
+    let assembly =
+        releases::pin_release(&state, &entity, group_id, &name, request.assembly_id).await?;
+
+    Ok((StatusCode::CREATED, Json(assembly)).into_response())
+}
+
+pub async fn unpin_release(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Response, AppError> {
+    releases::unpin_release(&state, &entity, group_id, &name).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn list_releases(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let releases = releases::list_releases(&state, &entity, group_id).await?;
+
+    Ok(Json(releases).into_response())
+}
+
+/// the archive is named after the release rather than the assembly id
+pub async fn download_release(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    headers: HeaderMap,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Response, AppError> {
+    // checked before the name is resolved, so releases can't be probed for by who can't read them
+    if !PermissionChecker::new(&state.db, &entity)
+        .can_any(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?
+    {
+        return Err(Error::Forbidden.into());
+    }
+
+    let id = database::assembly::get_release_assembly_id(&state.db, group_id, &name)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("release {name} not found")))?;
+
+    assembler::require_assembly_access(&state, &entity, group_id, id).await?;
+
+    serve_assembly(&state, &headers, group_id, id, &name).await
+}
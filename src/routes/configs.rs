@@ -0,0 +1,64 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    configs, database,
+    errors::{AppError, Error},
+    server::AppState,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ConfigQuery {
+    pub revision: Option<u32>,
+}
+
+pub async fn list_configs(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    PermissionChecker::new(&state.db, &entity)
+        .require(PermissionCheck::new(ResourceType::Config, Action::List).in_group(group_id))
+        .await?;
+
+    let templates = database::configs::list_config_templates(&state.db, group_id).await?;
+    Ok(Json(templates).into_response())
+}
+
+pub async fn get_config(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, path)): Path<(Uuid, String)>,
+    Query(query): Query<ConfigQuery>,
+) -> Result<Response, AppError> {
+    PermissionChecker::new(&state.db, &entity)
+        .require(PermissionCheck::new(ResourceType::Config, Action::Get).in_group(group_id))
+        .await?;
+
+    match database::configs::get_config_version(&state.db, group_id, &path, query.revision).await {
+        Ok(Some(version)) => Ok(Json(version).into_response()),
+        Ok(None) => Err(Error::NotFound(format!("config {path} not found")).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// the request body is the raw template, every upload becomes a new revision
+pub async fn save_config(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, path)): Path<(Uuid, String)>,
+    content: String,
+) -> Result<Response, AppError> {
+    let version = configs::save_config(&state, &entity, group_id, &path, &content).await?;
+    Ok((StatusCode::CREATED, Json(version)).into_response())
+}
@@ -0,0 +1,212 @@
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    errors::{AppError, Error},
+    models::{plugins::Blob, signing::DetachedSignature},
+    server::AppState,
+    signing,
+    storage::StorageError,
+};
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    /// inclusive on both ends, like `Content-Range`
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// only a single `bytes=` range is served, anything else falls back to the whole blob,
+/// which the spec allows
+fn parse_range(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+        // bytes=-500 is the last 500 bytes
+        (Err(_), Ok(suffix)) if start.is_empty() => match suffix {
+            0 => return ByteRange::Unsatisfiable,
+            n => (size.saturating_sub(n), size.saturating_sub(1)),
+        },
+        (Ok(start), Err(_)) if end.is_empty() => (start, size.saturating_sub(1)),
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+        _ => return ByteRange::Full,
+    };
+
+    if size == 0 || range.0 >= size {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(range.0, range.1)
+    }
+}
+
+/// `If-None-Match` and `If-Range` carry lists of entity tags, weak ones compare by value
+fn etag_matches(value: &HeaderValue, etag: &str) -> bool {
+    value.to_str().is_ok_and(|list| {
+        list.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        })
+    })
+}
+
+/// whether a `serve_blob` response sends the blob from its first byte
+pub fn starts_download(response: &Response) -> bool {
+    match response.status() {
+        StatusCode::OK => true,
+        StatusCode::PARTIAL_CONTENT => response
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|range| range.starts_with("bytes 0-")),
+        _ => false,
+    }
+}
+
+/// streams a blob from storage with `ETag` (its sha256), `Range`/`If-Range` and
+/// `If-None-Match` support. the body is never held in memory.
+pub async fn serve_blob(
+    state: &AppState,
+    headers: &HeaderMap,
+    blob: &Blob,
+    content_type: &'static str,
+    filename: &str,
+    signature: &DetachedSignature,
+) -> Result<Response, AppError> {
+    let etag = format!("\"{}\"", blob.sha256);
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ETAG, header_value(&etag)?);
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_headers.insert(
+        signing::SIGNATURE_HEADER,
+        header_value(&signature.signature)?,
+    );
+    response_headers.insert(signing::KEY_ID_HEADER, header_value(&signature.key_id)?);
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| etag_matches(value, &etag))
+    {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    let mut file = state.storage.open(blob.id).await.map_err(|e| match e {
+        StorageError::NotFound(_) => Error::NotFound(format!("blob {} not found", blob.id)),
+        e => Error::Storage(e),
+    })?;
+    let size = file.metadata().await.map_err(StorageError::from)?.len();
+
+    // a resumed download only continues if the blob is still the one it started with
+    let range_applies = headers
+        .get(header::IF_RANGE)
+        .is_none_or(|value| etag_matches(value, &etag));
+    let range = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+        Some(value) if range_applies => parse_range(value, size),
+        _ => ByteRange::Full,
+    };
+
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_headers.insert(
+        header::CONTENT_DISPOSITION,
+        header_value(&format!("attachment; filename=\"{filename}\""))?,
+    );
+
+    let (status, start, len) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, size),
+        ByteRange::Partial(start, end) => {
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes {start}-{end}/{size}"))?,
+            );
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        ByteRange::Unsatisfiable => {
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes */{size}"))?,
+            );
+            return Ok((StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response());
+        }
+    };
+
+    file.seek(std::io::SeekFrom::Start(start))
+        .await
+        .map_err(StorageError::from)?;
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+
+    let body = Body::from_stream(ReaderStream::new(file.take(len)));
+
+    Ok((status, response_headers, body).into_response())
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, Error> {
+    HeaderValue::from_str(value).map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            ByteRange::Partial(500, 999)
+        );
+    }
+
+    #[test]
+    fn open_ended_range() {
+        assert_eq!(
+            parse_range("bytes=100-", 1000),
+            ByteRange::Partial(100, 999)
+        );
+    }
+
+    #[test]
+    fn suffix_range() {
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=-5000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn unsatisfiable_range() {
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(
+            parse_range("bytes=2000-3000", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn multiple_ranges_fall_back_to_full() {
+        assert_eq!(parse_range("bytes=0-99,200-299", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn malformed_ranges_fall_back_to_full() {
+        assert_eq!(parse_range("items=0-99", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=99-0", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=abc", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=-", 1000), ByteRange::Full);
+    }
+}
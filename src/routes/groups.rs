@@ -1,7 +1,7 @@
 use askama::Template;
 use axum::{
     Form,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::HeaderMap,
     response::Html,
 };
@@ -10,6 +10,7 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
+    assembler,
     auth::{
         auth::AuthSession,
         extractor::AuthenticatedEntity,
@@ -24,6 +25,7 @@ use crate::{
     errors::{AppError, Error},
     models::{
         self,
+        assembly::{Assembly, AssemblyDiff},
         auth::{MachineKeyPermission, PublicMachineKey, PublicUser, User},
         groups::{GroupMachineKey, GroupMember},
         plugins::GroupPluginSummary,
@@ -329,6 +331,116 @@ pub async fn groups_plugins(
     Ok(Html(html))
 }
 
+#[derive(Template)]
+#[template(path = "groups/assembly.jinja")]
+struct GroupAssemblyTemplate {
+    group: GroupEntry,
+    assembly: Assembly,
+    settings: InstanceSettings,
+    messages: Vec<Message>,
+    current_user: Option<PublicUser>,
+    active_tab: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "groups/partials/assembly_content.jinja")]
+struct GroupAssemblyPartialTemplate {
+    group: GroupEntry,
+    assembly: Assembly,
+}
+
+/// the partial polls itself while the assembly is pending or running
+pub async fn groups_assembly(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    messages: Messages,
+    headers: HeaderMap,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Html<String>, AppError> {
+    let (group, user) = load_group_context(
+        &state,
+        &auth,
+        group_id,
+        Some(PermissionCheck::new(ResourceType::Artifact, Action::Get).with_resource_id(id)),
+    )
+    .await?;
+
+    let assembly = database::assembly::get_assembly(&state.db, group_id, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("assembly {id} not found")))?;
+
+    let html = if headers.contains_key("hx-request") {
+        GroupAssemblyPartialTemplate { group, assembly }.render()?
+    } else {
+        GroupAssemblyTemplate {
+            group,
+            assembly,
+            settings: state.settings.read().await.clone(),
+            messages: messages.into_iter().collect(),
+            current_user: Some(user.into()),
+            active_tab: "",
+        }
+        .render()?
+    };
+
+    Ok(Html(html))
+}
+
+#[derive(Template)]
+#[template(path = "groups/assembly_diff.jinja")]
+struct GroupAssemblyDiffTemplate {
+    group: GroupEntry,
+    diff: AssemblyDiff,
+    settings: InstanceSettings,
+    messages: Vec<Message>,
+    current_user: Option<PublicUser>,
+    active_tab: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "groups/partials/assembly_diff_content.jinja")]
+struct GroupAssemblyDiffPartialTemplate {
+    group: GroupEntry,
+    diff: AssemblyDiff,
+}
+
+#[derive(Deserialize)]
+pub struct AssemblyDiffQuery {
+    from: Uuid,
+    to: Uuid,
+}
+
+pub async fn groups_assembly_diff(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    messages: Messages,
+    headers: HeaderMap,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<AssemblyDiffQuery>,
+) -> Result<Html<String>, AppError> {
+    let (group, user) = load_group_context(&state, &auth, group_id, None).await?;
+
+    let entity = AuthenticatedEntity::User(user.clone());
+    let diff =
+        assembler::diff::diff_assemblies(&state, &entity, group_id, query.from, query.to).await?;
+
+    let html = if headers.contains_key("hx-request") {
+        GroupAssemblyDiffPartialTemplate { group, diff }.render()?
+    } else {
+        GroupAssemblyDiffTemplate {
+            group,
+            diff,
+            settings: state.settings.read().await.clone(),
+            messages: messages.into_iter().collect(),
+            current_user: Some(user.into()),
+            active_tab: "",
+        }
+        .render()?
+    };
+
+    Ok(Html(html))
+}
+
 #[derive(Template)]
 #[template(path = "groups/machinekeys.jinja")]
 struct GroupMachineKeysTemplate {
@@ -0,0 +1,138 @@
+use std::net::IpAddr;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    assembler::ArtifactCoordinate,
+    auth::extractor::AuthenticatedEntity,
+    errors::AppError,
+    links,
+    middleware::client_ip::ClientIp,
+    models::links::LinkTarget,
+    routes::{assembler, download, plugins},
+    server::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct CreateLink {
+    /// an hour if not set, at most a week
+    pub expires_in_secs: Option<u64>,
+    pub bind_ip: Option<IpAddr>,
+    pub max_uses: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct SignedLink {
+    pub expires: i64,
+    pub sig: String,
+}
+
+async fn create_link(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    target: LinkTarget,
+    request: CreateLink,
+) -> Result<Response, AppError> {
+    let link = links::create_link(
+        state,
+        entity,
+        group_id,
+        target,
+        request.expires_in_secs,
+        request.bind_ip,
+        request.max_uses,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(link)).into_response())
+}
+
+pub async fn create_assembly_link(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<CreateLink>,
+) -> Result<Response, AppError> {
+    create_link(&state, &entity, group_id, LinkTarget::Assembly(id), request).await
+}
+
+pub async fn create_plugin_link(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_group_id, artifact_id, version)): Path<(Uuid, String, String, String)>,
+    Json(request): Json<CreateLink>,
+) -> Result<Response, AppError> {
+    let coord = ArtifactCoordinate {
+        group_id: plugin_group_id,
+        artifact_id,
+        version,
+    };
+    create_link(
+        &state,
+        &entity,
+        group_id,
+        LinkTarget::Artifact(coord),
+        request,
+    )
+    .await
+}
+
+pub async fn list_links(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let links = links::list_links(&state, &entity, group_id).await?;
+    Ok(Json(links).into_response())
+}
+
+pub async fn revoke_link(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    links::revoke_link(&state, &entity, group_id, id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// the signed url is the only credential, no session or machine key is needed
+pub async fn download_link(
+    State(state): State<AppState>,
+    Extension(ClientIp(client_ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(signed): Query<SignedLink>,
+) -> Result<Response, AppError> {
+    let link = links::redeem(&state, id, signed.expires, &signed.sig, client_ip).await?;
+
+    let response = match link.target {
+        LinkTarget::Assembly(assembly_id) => {
+            assembler::serve_assembly(
+                &state,
+                &headers,
+                link.group_id,
+                assembly_id,
+                &format!("assembly-{assembly_id}"),
+            )
+            .await
+        }
+        LinkTarget::Artifact(coord) => {
+            plugins::serve_plugin(&state, &headers, link.group_id, &coord).await
+        }
+    }?;
+
+    // resumed ranges and conditional hits belong to a download that was already counted
+    if download::starts_download(&response) {
+        links::record_use(&state, id).await?;
+    }
+
+    Ok(response)
+}
@@ -2,6 +2,12 @@ pub mod admin;
 pub mod assembler;
 pub mod assets;
 pub mod auth;
+pub mod configs;
+pub mod download;
 pub mod files;
 pub mod groups;
+pub mod links;
 pub mod plugins;
+pub mod secrets;
+pub mod signing;
+pub mod webhooks;
@@ -1,18 +1,25 @@
 use axum::{
     Json,
     extract::{Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
+    assembler::ArtifactCoordinate,
     auth::{
         extractor::AuthenticatedEntity,
         permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
     },
+    database,
     errors::{AppError, Error},
-    plugins::{self, UploadPluginOptions},
+    models::plugins::{PluginShare, SharedPlugin},
+    plugins::{self, UploadPluginOptions, sharing},
+    routes::download,
     server::AppState,
+    signing,
 };
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +27,8 @@ pub struct PluginMetadata {
     pub artifact_id: String,
     pub group_id: String,
     pub version: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -97,6 +106,7 @@ pub async fn plugin_upload(
             plugin_artifact_id: metadata.artifact_id.clone(),
             plugin_group_id: metadata.group_id.clone(),
             version: metadata.version.clone(),
+            game_versions: metadata.game_versions.clone(),
             file: file.into(),
         },
     )
@@ -110,3 +120,124 @@ pub async fn plugin_upload(
         version: metadata.version,
     }))
 }
+
+pub async fn plugin_download(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    headers: HeaderMap,
+    Path((group_id, plugin_group_id, artifact_id, version)): Path<(Uuid, String, String, String)>,
+) -> Result<Response, AppError> {
+    PermissionChecker::new(&state.db, &entity)
+        .require(PermissionCheck::new(ResourceType::Artifact, Action::Get).in_group(group_id))
+        .await?;
+
+    let coord = ArtifactCoordinate {
+        group_id: plugin_group_id,
+        artifact_id,
+        version,
+    };
+
+    serve_plugin(&state, &headers, group_id, &coord).await
+}
+
+pub async fn serve_plugin(
+    state: &AppState,
+    headers: &HeaderMap,
+    group_id: Uuid,
+    coord: &ArtifactCoordinate,
+) -> Result<Response, AppError> {
+    let resolved = database::assembly::get_artifact(&state.db, coord, group_id)
+        .await
+        .map_err(Error::from)?;
+
+    let blob_id = resolved
+        .version
+        .blob_id
+        .ok_or_else(|| Error::NotFound(format!("{coord} is not cached")))?;
+
+    let blob = database::blobs::get_blob(&state.db, blob_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("blob {blob_id} not found")))?;
+    let signature = signing::sign(state, group_id, &blob.sha256).await?;
+
+    download::serve_blob(
+        state,
+        headers,
+        &blob,
+        "application/java-archive",
+        &format!("{}-{}.jar", coord.artifact_id, coord.version),
+        &signature,
+    )
+    .await
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnpublishResponse {
+    /// groups that had it attached and lost access
+    pub detached: u64,
+}
+
+pub async fn publish_plugin(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    sharing::publish_plugin(&state, &entity, group_id, plugin_id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn unpublish_plugin(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<UnpublishResponse>, AppError> {
+    let detached = sharing::unpublish_plugin(&state, &entity, group_id, plugin_id).await?;
+    Ok(Json(UnpublishResponse { detached }))
+}
+
+pub async fn list_plugin_shares(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<PluginShare>>, AppError> {
+    Ok(Json(
+        sharing::list_plugin_shares(&state, &entity, group_id, plugin_id).await?,
+    ))
+}
+
+pub async fn revoke_plugin_share(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id, target_group_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    sharing::revoke_plugin_share(&state, &entity, group_id, plugin_id, target_group_id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn list_shared_plugins(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<Vec<SharedPlugin>>, AppError> {
+    Ok(Json(
+        sharing::list_shared_plugins(&state, &entity, group_id).await?,
+    ))
+}
+
+pub async fn attach_plugin(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    sharing::attach_plugin(&state, &entity, group_id, plugin_id).await?;
+    Ok(StatusCode::CREATED.into_response())
+}
+
+pub async fn detach_plugin(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, plugin_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    sharing::detach_plugin(&state, &entity, group_id, plugin_id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
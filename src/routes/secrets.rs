@@ -0,0 +1,38 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use crate::{auth::extractor::AuthenticatedEntity, errors::AppError, secrets, server::AppState};
+
+pub async fn list_secrets(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let secrets = secrets::list_secrets(&state, &entity, group_id).await?;
+    Ok(Json(secrets).into_response())
+}
+
+/// the request body is the raw secret value. it is write-only, there is no endpoint to read it back
+pub async fn put_secret(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+    value: String,
+) -> Result<Response, AppError> {
+    secrets::put_secret(&state, &entity, group_id, &name, &value).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn delete_secret(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, name)): Path<(Uuid, String)>,
+) -> Result<Response, AppError> {
+    secrets::delete_secret(&state, &entity, group_id, &name).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
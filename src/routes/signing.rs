@@ -0,0 +1,41 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use crate::{auth::extractor::AuthenticatedEntity, errors::AppError, server::AppState, signing};
+
+/// public, so servers can fetch the key they verify downloads against
+pub async fn well_known_key(State(state): State<AppState>) -> Response {
+    Json(signing::instance_public_key(&state)).into_response()
+}
+
+pub async fn get_group_key(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let key = signing::get_group_key(&state, &entity, group_id).await?;
+    Ok(Json(key).into_response())
+}
+
+pub async fn rotate_group_key(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let key = signing::rotate_group_key(&state, &entity, group_id).await?;
+    Ok((StatusCode::CREATED, Json(key)).into_response())
+}
+
+pub async fn delete_group_key(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    signing::delete_group_key(&state, &entity, group_id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
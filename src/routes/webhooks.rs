@@ -0,0 +1,53 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{auth::extractor::AuthenticatedEntity, errors::AppError, server::AppState, webhooks};
+
+#[derive(Deserialize)]
+pub struct CreateWebhook {
+    pub url: String,
+}
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let webhooks = webhooks::list_webhooks(&state, &entity, group_id).await?;
+    Ok(Json(webhooks).into_response())
+}
+
+/// the response holds the signing secret, it can't be read back later
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path(group_id): Path<Uuid>,
+    Json(request): Json<CreateWebhook>,
+) -> Result<Response, AppError> {
+    let webhook = webhooks::create_webhook(&state, &entity, group_id, &request.url).await?;
+    Ok((StatusCode::CREATED, Json(webhook)).into_response())
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    webhooks::delete_webhook(&state, &entity, group_id, id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    entity: AuthenticatedEntity,
+    Path((group_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    let deliveries = webhooks::list_deliveries(&state, &entity, group_id, id).await?;
+    Ok(Json(deliveries).into_response())
+}
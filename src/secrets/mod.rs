@@ -0,0 +1,182 @@
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use base64::{Engine as _, engine::general_purpose};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::secrets::GroupSecret,
+    server::AppState,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SecretError {
+    #[error("secret store is not configured")]
+    NotConfigured,
+    #[error("failed to encrypt secret '{0}'")]
+    Encrypt(String),
+    #[error("failed to decrypt secret '{0}'")]
+    Decrypt(String),
+    #[error("secret '{0}' not found")]
+    NotFound(String),
+}
+
+#[derive(Clone)]
+pub struct SecretsKey(Key<Aes256Gcm>);
+
+impl std::fmt::Debug for SecretsKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretsKey([redacted])")
+    }
+}
+
+pub struct EncryptedSecret {
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl SecretsKey {
+    /// expects 32 random bytes, base64 encoded
+    pub fn from_base64(encoded: &str) -> Result<Self, String> {
+        let bytes = general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("not valid base64: {e}"))?;
+        if bytes.len() != 32 {
+            return Err(format!("must be 32 bytes, got {}", bytes.len()));
+        }
+
+        Ok(Self(*Key::<Aes256Gcm>::from_slice(&bytes)))
+    }
+
+    pub fn encrypt(
+        &self,
+        group_id: Uuid,
+        name: &str,
+        value: &str,
+    ) -> Result<EncryptedSecret, SecretError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(group_id, name);
+        let ciphertext = Aes256Gcm::new(&self.0)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| SecretError::Encrypt(name.to_string()))?;
+
+        Ok(EncryptedSecret {
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    pub fn decrypt(
+        &self,
+        group_id: Uuid,
+        name: &str,
+        secret: &EncryptedSecret,
+    ) -> Result<String, SecretError> {
+        if secret.nonce.len() != 12 {
+            return Err(SecretError::Decrypt(name.to_string()));
+        }
+
+        let aad = associated_data(group_id, name);
+        let plaintext = Aes256Gcm::new(&self.0)
+            .decrypt(
+                Nonce::from_slice(&secret.nonce),
+                Payload {
+                    msg: &secret.ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| SecretError::Decrypt(name.to_string()))?;
+
+        String::from_utf8(plaintext).map_err(|_| SecretError::Decrypt(name.to_string()))
+    }
+}
+
+/// binds a ciphertext to its group and name, so rows can't be swapped around in the database
+fn associated_data(group_id: Uuid, name: &str) -> String {
+    format!("{group_id}/{name}")
+}
+
+/// secret names are referenced as `{{ secret.<name> }}`, so no dots or whitespace
+pub fn validate_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!("invalid secret name: {name}")))
+    }
+}
+
+pub async fn put_secret(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+    value: &str,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Secret, Action::Manage).in_group(group_id))
+        .await?;
+
+    validate_name(name)?;
+
+    let key = state
+        .config
+        .secrets_key
+        .as_ref()
+        .ok_or(SecretError::NotConfigured)?;
+    let encrypted = key.encrypt(group_id, name, value)?;
+
+    database::secrets::upsert_secret(&state.db, group_id, name, &encrypted).await?;
+
+    tracing::info!(group_id = %group_id, name, "group secret saved");
+
+    Ok(())
+}
+
+pub async fn delete_secret(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    name: &str,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Secret, Action::Delete).in_group(group_id))
+        .await?;
+
+    if !database::secrets::delete_secret(&state.db, group_id, name).await? {
+        return Err(SecretError::NotFound(name.to_string()).into());
+    }
+
+    tracing::info!(group_id = %group_id, name, "group secret deleted");
+
+    Ok(())
+}
+
+pub async fn list_secrets(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<GroupSecret>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Secret, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::secrets::list_secrets(&state.db, group_id).await?)
+}
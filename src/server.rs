@@ -1,6 +1,9 @@
 use crate::{
     Config,
-    assembler::{job::AssemblyJob, worker},
+    assembler::{
+        events::AssemblyWatchers,
+        worker::{self, WorkerContext},
+    },
     auth::auth::Backend,
     database::settings::load_instance_settings,
     errors::Error,
@@ -8,7 +11,7 @@ use crate::{
     routes::{
         admin, assembler, assets,
         auth::{login_page, login_post},
-        files, groups, plugins,
+        configs, files, groups, links, plugins, secrets, signing, webhooks,
     },
     storage::filesystem::FilesystemStorage,
 };
@@ -18,7 +21,7 @@ use axum::{
     extract::DefaultBodyLimit,
     http::StatusCode,
     middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 use axum_login::tower_sessions::ExpiredDeletion;
 use axum_login::{
@@ -27,6 +30,7 @@ use axum_login::{
     tower_sessions::SessionManagerLayer,
 };
 use axum_messages::MessagesManagerLayer;
+use ed25519_dalek::SigningKey;
 use governor::DefaultKeyedRateLimiter;
 use sqlx::SqlitePool;
 use std::{
@@ -37,7 +41,7 @@ use time::Duration;
 use tokio::{
     net::TcpListener,
     signal,
-    sync::{RwLock, mpsc},
+    sync::{Notify, RwLock},
     task::AbortHandle,
 };
 use tower::{BoxError, ServiceBuilder};
@@ -46,14 +50,26 @@ use tower_http::{limit::RequestBodyLimitLayer, trace::TraceLayer};
 use tower_sessions::Expiry;
 use tower_sessions_sqlx_store::SqliteStore;
 
+/// time to produce a response, a streamed body isn't cut off once it has started
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// downloads may build a delta or wait on a cold disk before the first byte
+const DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db: SqlitePool,
     pub config: Arc<Config>,
     pub storage: FilesystemStorage,
     pub settings: Arc<RwLock<InstanceSettings>>,
-    pub assembly_tx: mpsc::Sender<AssemblyJob>,
+    /// wakes the assembly worker, the queue itself is the `assemblies` table
+    pub assembly_notify: Arc<Notify>,
     pub active_assembly_jobs: Arc<AtomicUsize>,
+    /// wakes assembly event streams
+    pub assembly_watchers: AssemblyWatchers,
+    /// signs downloads of groups without a key of their own
+    pub signing_key: Arc<SigningKey>,
+    /// hmac key of pre-signed download links
+    pub link_key: Arc<[u8; 32]>,
 }
 
 pub struct Server {
@@ -121,25 +137,52 @@ impl Server {
 
         let settings = load_instance_settings(&self.db).await?;
 
-        const ASSEMBLY_QUEUE_SIZE: usize = 100;
-
-        let (assembly_tx, assembly_rx) = mpsc::channel::<AssemblyJob>(ASSEMBLY_QUEUE_SIZE);
+        let signing_key = crate::signing::load_or_create_key(
+            &self.config.data_folder.join(".cogere/signing.key"),
+        )?;
+        let link_key = crate::signing::load_or_create_secret(
+            &self.config.data_folder.join(".cogere/links.key"),
+        )?;
 
         let state = AppState {
             db: self.db,
             config: Arc::new(self.config.clone()),
             storage: FilesystemStorage::new(self.config.data_folder),
             settings: Arc::new(RwLock::new(settings)),
-            assembly_tx,
+            assembly_notify: Arc::new(Notify::new()),
             active_assembly_jobs: Arc::new(AtomicUsize::new(0)),
+            assembly_watchers: AssemblyWatchers::default(),
+            signing_key: Arc::new(signing_key),
+            link_key: Arc::new(link_key),
         };
 
-        tokio::spawn(worker::run(
-            assembly_rx,
+        // leftovers from builds that were running when a process died
+        let stale_after =
+            std::time::Duration::from_secs(state.settings.read().await.assembly_timeout_secs);
+        state.storage.clear_stale_temp(stale_after).await?;
+
+        if self.config.assembly_workers == Some(0) {
+            tracing::info!("local assembly workers disabled, waiting on standalone workers");
+        } else {
+            tokio::spawn(worker::run(
+                WorkerContext {
+                    pool: state.db.clone(),
+                    settings: state.settings.clone(),
+                    storage: state.storage.clone(),
+                    active_jobs: state.active_assembly_jobs.clone(),
+                    watchers: state.assembly_watchers.clone(),
+                    secrets_key: state.config.secrets_key.clone(),
+                    public_base_url: state.config.public_base_url.clone(),
+                    max_workers: state.config.assembly_workers,
+                },
+                state.assembly_notify.clone(),
+            ));
+        }
+
+        tokio::spawn(crate::webhooks::run(
             state.db.clone(),
             state.settings.clone(),
-            state.storage.clone(),
-            state.active_assembly_jobs.clone(),
+            state.config.secrets_key.clone(),
         ));
 
         tokio::spawn(crate::assembler::cleanup::run(
@@ -171,6 +214,10 @@ impl Server {
                 "/api/v1/groups/{group_id}/assemble",
                 post(assembler::request_assembly),
             )
+            .route(
+                "/api/v1/groups/{group_id}/profiles/{name}/assemble",
+                post(assembler::assemble_profile),
+            )
             .route_layer(assemble_limiter);
 
         let download_conf = Box::new(
@@ -189,7 +236,25 @@ impl Server {
                 "/api/v1/groups/{group_id}/assemblies/{id}/download",
                 get(assembler::download_assembly),
             )
-            .route_layer(download_limiter);
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/{id}/delta",
+                post(assembler::download_delta),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/releases/{name}/download",
+                get(assembler::download_release),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/plugins/{plugin_group_id}/{artifact_id}/{version}/download",
+                get(plugins::plugin_download),
+            )
+            .route("/dl/{id}", get(links::download_link))
+            .route_layer(download_limiter)
+            .layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_timeout))
+                    .timeout(DOWNLOAD_TIMEOUT),
+            );
 
         let general_conf = Box::new(
             GovernorConfigBuilder::default()
@@ -224,6 +289,14 @@ impl Server {
             )
             .route("/g/{group_id}/members", get(groups::groups_members))
             .route("/g/{group_id}/plugins", get(groups::groups_plugins))
+            .route(
+                "/g/{group_id}/assemblies/{id}",
+                get(groups::groups_assembly),
+            )
+            .route(
+                "/g/{group_id}/assemblies/diff",
+                get(groups::groups_assembly_diff),
+            )
             .route_layer(general_limiter.clone())
             .merge(admin_routes)
             .route_layer(require_login);
@@ -231,36 +304,137 @@ impl Server {
         let api_routes = Router::new()
             .route(
                 "/api/v1/groups/{group_id}/assemblies/{id}",
-                get(assembler::get_assembly),
+                get(assembler::get_assembly).delete(assembler::cancel_assembly),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/diff",
+                get(assembler::diff_assemblies),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/{id}/events",
+                get(assembler::assembly_events),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/{id}/cancel",
+                post(assembler::cancel_assembly),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/{id}/signature",
+                get(assembler::assembly_signature),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/releases",
+                get(assembler::list_releases),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/releases/{name}",
+                put(assembler::pin_release).delete(assembler::unpin_release),
             )
             .route(
                 "/api/v1/groups/{group_id}/plugins",
                 post(plugins::plugin_upload),
             )
+            .route(
+                "/api/v1/groups/{group_id}/profiles",
+                get(assembler::list_profiles),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/profiles/{name}",
+                get(assembler::get_profile)
+                    .put(assembler::save_profile)
+                    .delete(assembler::delete_profile),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/profiles/{name}/assemblies",
+                get(assembler::list_profile_runs),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/sharing/published/{plugin_id}",
+                get(plugins::list_plugin_shares)
+                    .put(plugins::publish_plugin)
+                    .delete(plugins::unpublish_plugin),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/sharing/published/{plugin_id}/{target_group_id}",
+                delete(plugins::revoke_plugin_share),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/sharing/available",
+                get(plugins::list_shared_plugins),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/sharing/attached/{plugin_id}",
+                post(plugins::attach_plugin).delete(plugins::detach_plugin),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/configs",
+                get(configs::list_configs),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/configs/{*path}",
+                get(configs::get_config).put(configs::save_config),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/secrets",
+                get(secrets::list_secrets),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/secrets/{name}",
+                put(secrets::put_secret).delete(secrets::delete_secret),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/webhooks",
+                get(webhooks::list_webhooks).post(webhooks::create_webhook),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/webhooks/{id}",
+                delete(webhooks::delete_webhook),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/webhooks/{id}/deliveries",
+                get(webhooks::list_deliveries),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/signing-key",
+                get(signing::get_group_key)
+                    .post(signing::rotate_group_key)
+                    .delete(signing::delete_group_key),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/assemblies/{id}/links",
+                post(links::create_assembly_link),
+            )
+            .route(
+                "/api/v1/groups/{group_id}/plugins/{plugin_group_id}/{artifact_id}/{version}/links",
+                post(links::create_plugin_link),
+            )
+            .route("/api/v1/groups/{group_id}/links", get(links::list_links))
+            .route(
+                "/api/v1/groups/{group_id}/links/{id}",
+                delete(links::revoke_link),
+            )
             .route_layer(general_limiter)
-            .merge(assemble_routes)
-            .merge(download_routes);
+            .merge(assemble_routes);
 
         let app = Router::new()
             .merge(ui_routes)
             .merge(api_routes)
             .route("/assets/{*path}", get(assets::serve_asset))
+            .route(
+                "/.well-known/cogere-signing-key",
+                get(signing::well_known_key),
+            )
             .route("/login", get(login_page).post(login_post))
+            .layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_timeout))
+                    .timeout(REQUEST_TIMEOUT),
+            )
+            .merge(download_routes)
             .layer(MessagesManagerLayer)
             .layer(auth_layer)
             .layer(
                 ServiceBuilder::new()
-                    .layer(HandleErrorLayer::new(|error: BoxError| async move {
-                        if error.is::<tower::timeout::error::Elapsed>() {
-                            Ok(StatusCode::REQUEST_TIMEOUT)
-                        } else {
-                            Err((
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                format!("Unhandled internal error: {error}"),
-                            ))
-                        }
-                    }))
-                    .timeout(std::time::Duration::from_secs(10))
                     .layer(
                         TraceLayer::new_for_http()
                             .make_span_with(move |request: &axum::http::Request<_>| {
@@ -319,6 +493,65 @@ impl Server {
 
         Ok(())
     }
+
+    /// runs only the assembly worker. the host needs the same database and data folder as
+    /// the web process, jobs are claimed through leases so any number of workers can run.
+    pub async fn work(self) -> Result<(), Box<dyn std::error::Error>> {
+        let settings = Arc::new(RwLock::new(load_instance_settings(&self.db).await?));
+        let storage = FilesystemStorage::new(self.config.data_folder.clone());
+
+        let stale_after =
+            std::time::Duration::from_secs(settings.read().await.assembly_timeout_secs);
+        storage.clear_stale_temp(stale_after).await?;
+
+        tokio::spawn(refresh_settings(self.db.clone(), settings.clone()));
+
+        let ctx = WorkerContext {
+            pool: self.db,
+            settings,
+            storage,
+            active_jobs: Arc::new(AtomicUsize::new(0)),
+            // event streams live in the web process, there's nobody here to wake
+            watchers: AssemblyWatchers::default(),
+            secrets_key: self.config.secrets_key,
+            public_base_url: self.config.public_base_url,
+            max_workers: self.config.assembly_workers,
+        };
+
+        tracing::info!("assembly worker started");
+
+        // nothing notifies across processes, new requests are picked up by polling.
+        // builds still running on shutdown are retried elsewhere once their lease expires.
+        tokio::select! {
+            _ = worker::run(ctx, Arc::new(Notify::new())) => {}
+            _ = signal::ctrl_c() => {}
+        }
+
+        Ok(())
+    }
+}
+
+async fn handle_timeout(error: BoxError) -> Result<StatusCode, (StatusCode, String)> {
+    if error.is::<tower::timeout::error::Elapsed>() {
+        Ok(StatusCode::REQUEST_TIMEOUT)
+    } else {
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unhandled internal error: {error}"),
+        ))
+    }
+}
+
+/// standalone workers have no admin page to reload from, so they pick up changes on their own
+async fn refresh_settings(db: SqlitePool, settings: Arc<RwLock<InstanceSettings>>) {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        match load_instance_settings(&db).await {
+            Ok(fresh) => *settings.write().await = fresh,
+            Err(e) => tracing::warn!(error = %e, "failed to refresh instance settings"),
+        }
+    }
 }
 
 async fn shutdown_signal(deletion_task_abort_handle: AbortHandle) {
@@ -0,0 +1,232 @@
+use std::{io::Write as _, path::Path};
+
+use axum::http::HeaderName;
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::signing::{DetachedSignature, PublicSigningKey},
+    secrets::SecretError,
+    server::AppState,
+};
+
+pub const ALGORITHM: &str = "ed25519";
+pub const SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-cogere-signature");
+pub const KEY_ID_HEADER: HeaderName = HeaderName::from_static("x-cogere-key-id");
+
+/// group keys are stored like secrets, under a name no secret can have
+const GROUP_KEY_NAME: &str = ".signing-key";
+
+/// der prefix of an ed25519 SubjectPublicKeyInfo, the raw key follows
+const SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// 32 random bytes kept next to the database as base64, generated on first start
+pub fn load_or_create_secret(path: &Path) -> std::io::Result<[u8; 32]> {
+    match std::fs::read_to_string(path) {
+        Ok(encoded) => general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{} is not a valid key file", path.display()),
+                )
+            }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let secret: [u8; 32] = rand::random();
+
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+            let mut file = options.open(path)?;
+            file.write_all(general_purpose::STANDARD.encode(secret).as_bytes())?;
+
+            tracing::info!(path = %path.display(), "generated key file");
+
+            Ok(secret)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// the instance key. standalone workers don't need it, downloads are signed by the server.
+pub fn load_or_create_key(path: &Path) -> std::io::Result<SigningKey> {
+    load_or_create_secret(path).map(|seed| SigningKey::from_bytes(&seed))
+}
+
+/// first 8 bytes of the sha256 of the raw public key, hex
+pub fn key_id(key: &VerifyingKey) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+fn pem(key: &VerifyingKey) -> String {
+    let mut der = SPKI_PREFIX.to_vec();
+    der.extend_from_slice(key.as_bytes());
+    format!(
+        "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+        general_purpose::STANDARD.encode(der)
+    )
+}
+
+fn public_key(
+    key: &VerifyingKey,
+    group_id: Option<Uuid>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> PublicSigningKey {
+    PublicSigningKey {
+        key_id: key_id(key),
+        algorithm: ALGORITHM,
+        public_key: general_purpose::STANDARD.encode(key.as_bytes()),
+        pem: pem(key),
+        group_id,
+        created_at,
+    }
+}
+
+pub fn instance_public_key(state: &AppState) -> PublicSigningKey {
+    public_key(&state.signing_key.verifying_key(), None, None)
+}
+
+/// the group's own key if it has one, the instance key otherwise
+async fn signing_key_for(state: &AppState, group_id: Uuid) -> Result<SigningKey, Error> {
+    let Some(row) = database::signing::get_group_signing_key(&state.db, group_id).await? else {
+        return Ok((*state.signing_key).clone());
+    };
+
+    let secrets_key = state
+        .config
+        .secrets_key
+        .as_ref()
+        .ok_or(SecretError::NotConfigured)?;
+
+    let seed: [u8; 32] = general_purpose::STANDARD
+        .decode(secrets_key.decrypt(group_id, GROUP_KEY_NAME, &row.seed)?)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SecretError::Decrypt(GROUP_KEY_NAME.to_string()))?;
+
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// detached signature over the raw 32 byte sha256 of a download, so it comes from the stored
+/// checksum and the blob itself can be streamed. verifiable with
+/// `openssl dgst -sha256 -binary <file> | openssl pkeyutl -verify -pubin -inkey key.pem -rawin -sigfile <sig>`
+pub async fn sign(
+    state: &AppState,
+    group_id: Uuid,
+    sha256: &str,
+) -> Result<DetachedSignature, Error> {
+    let digest =
+        decode_hex(sha256).ok_or_else(|| Error::Internal(format!("invalid sha256 {sha256}")))?;
+    let key = signing_key_for(state, group_id).await?;
+
+    Ok(DetachedSignature {
+        key_id: key_id(&key.verifying_key()),
+        algorithm: ALGORITHM,
+        signature: general_purpose::STANDARD.encode(key.sign(&digest).to_bytes()),
+        sha256: sha256.to_string(),
+    })
+}
+
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// the key the group's downloads are signed with
+pub async fn get_group_key(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<PublicSigningKey, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Group, Action::Get).in_group(group_id))
+        .await?;
+
+    match database::signing::get_group_signing_key(&state.db, group_id).await? {
+        Some(row) => {
+            let key = VerifyingKey::try_from(row.public_key.as_slice())
+                .map_err(|e| Error::Internal(format!("invalid group signing key: {e}")))?;
+            Ok(public_key(&key, Some(group_id), Some(row.created_at)))
+        }
+        None => Ok(instance_public_key(state)),
+    }
+}
+
+/// gives the group a key of its own, replacing any previous one. needs the secrets key, the
+/// private half is stored encrypted like a group secret.
+pub async fn rotate_group_key(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<PublicSigningKey, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Group, Action::Manage).in_group(group_id))
+        .await?;
+
+    let secrets_key = state
+        .config
+        .secrets_key
+        .as_ref()
+        .ok_or(SecretError::NotConfigured)?;
+
+    let key = SigningKey::from_bytes(&rand::random());
+    let seed = secrets_key.encrypt(
+        group_id,
+        GROUP_KEY_NAME,
+        &general_purpose::STANDARD.encode(key.to_bytes()),
+    )?;
+    let verifying_key = key.verifying_key();
+
+    database::signing::upsert_group_signing_key(
+        &state.db,
+        group_id,
+        verifying_key.as_bytes(),
+        &seed,
+    )
+    .await?;
+
+    tracing::info!(group_id = %group_id, key_id = %key_id(&verifying_key), "group signing key created");
+
+    get_group_key(state, entity, group_id).await
+}
+
+/// the group goes back to the instance key
+pub async fn delete_group_key(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Group, Action::Manage).in_group(group_id))
+        .await?;
+
+    if !database::signing::delete_group_signing_key(&state.db, group_id).await? {
+        return Err(Error::NotFound(
+            "group has no signing key of its own".into(),
+        ));
+    }
+
+    tracing::info!(group_id = %group_id, "group signing key deleted");
+
+    Ok(())
+}
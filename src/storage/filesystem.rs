@@ -1,4 +1,4 @@
-use std::{os::unix::fs::MetadataExt, path::PathBuf, sync::Arc};
+use std::{os::unix::fs::MetadataExt, path::PathBuf, sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use tokio::{
@@ -24,6 +24,103 @@ impl FilesystemStorage {
     fn path_for(&self, key: Uuid) -> PathBuf {
         self.root.join(key.to_string())
     }
+
+    /// lives next to the blobs so `persist` is a rename, not a copy
+    fn temp_dir(&self) -> PathBuf {
+        self.root.join(".cogere").join("tmp")
+    }
+
+    /// for blocking readers that stream a blob instead of loading it with `get`
+    pub fn open_blocking(&self, key: Uuid) -> Result<std::fs::File, StorageError> {
+        std::fs::File::open(self.path_for(key)).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                StorageError::NotFound(key)
+            } else {
+                StorageError::Io(e)
+            }
+        })
+    }
+
+    /// for streaming a blob out, e.g. as a response body
+    pub async fn open(&self, key: Uuid) -> Result<fs::File, StorageError> {
+        fs::File::open(self.path_for(key)).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                StorageError::NotFound(key)
+            } else {
+                StorageError::Io(e)
+            }
+        })
+    }
+
+    /// removes temp files untouched for `older_than`. the temp dir can be shared with worker
+    /// processes on other hosts, so fresh files may belong to a build that is still running.
+    pub async fn clear_stale_temp(&self, older_than: Duration) -> Result<(), StorageError> {
+        let mut entries = match fs::read_dir(self.temp_dir()).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let stale = entry
+                .metadata()
+                .await?
+                .modified()?
+                .elapsed()
+                .is_ok_and(|age| age > older_than);
+
+            if stale {
+                let _ = fs::remove_file(entry.path()).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn temp_file(&self) -> Result<TempFile, StorageError> {
+        let dir = self.temp_dir();
+        fs::create_dir_all(&dir).await?;
+
+        // read back for hashing once written, so not `File::create`, which is write-only
+        let path = dir.join(Uuid::now_v7().to_string());
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?
+            .into_std()
+            .await;
+
+        Ok(TempFile {
+            path: Some(path),
+            file,
+        })
+    }
+
+    /// moves a finished temp file into storage under `key`
+    pub async fn persist(&self, mut temp: TempFile, key: Uuid) -> Result<(), StorageError> {
+        let path = temp.path.take().expect("temp file already persisted");
+        if let Err(e) = fs::rename(&path, self.path_for(key)).await {
+            let _ = fs::remove_file(&path).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// a file in the storage temp dir, removed on drop unless it was persisted
+pub struct TempFile {
+    path: Option<PathBuf>,
+    pub file: std::fs::File,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
 
 impl LocalStorage for FilesystemStorage {
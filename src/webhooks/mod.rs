@@ -0,0 +1,403 @@
+use std::{net::IpAddr, sync::Arc, time::Duration};
+
+use base64::Engine as _;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tokio::sync::RwLock;
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        extractor::AuthenticatedEntity,
+        permissions::{Action, PermissionCheck, ResourceType, check::PermissionChecker},
+    },
+    database,
+    errors::Error,
+    models::{
+        assembly::Assembly,
+        settings::InstanceSettings,
+        webhooks::{CreatedWebhook, GroupWebhook, PendingDelivery, WebhookDelivery},
+    },
+    secrets::{SecretError, SecretsKey},
+    server::AppState,
+};
+
+/// how often due deliveries are looked for
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// a delivery is given up on after this many failed attempts, a bit over an hour in
+const MAX_ATTEMPTS: u32 = 8;
+const DELIVERY_LOG_DAYS: i64 = 30;
+
+pub const SIGNATURE_HEADER: &str = "x-cogere-signature";
+pub const EVENT_HEADER: &str = "x-cogere-event";
+pub const DELIVERY_HEADER: &str = "x-cogere-delivery";
+
+/// the secret's name for the associated data, a dot can't start a group secret's name
+fn secret_name(webhook_id: Uuid) -> String {
+    format!(".webhook/{webhook_id}")
+}
+
+#[derive(Serialize)]
+struct AssemblyEvent<'a> {
+    event: &'a str,
+    assembly: &'a Assembly,
+}
+
+/// addresses a webhook must never reach, so a url can't be used to probe the internal network
+fn is_internal(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // shared address space, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+        }
+    }
+}
+
+/// loopback is let through only with `webhook_allow_localhost`, for testing with a local receiver
+fn address_allowed(ip: IpAddr, allow_localhost: bool) -> bool {
+    if ip.to_canonical().is_loopback() {
+        allow_localhost
+    } else {
+        !is_internal(ip)
+    }
+}
+
+/// only http(s) urls whose host resolves to public addresses
+async fn validate_url(url: &str, allow_localhost: bool) -> Result<(), Error> {
+    let invalid = || Error::BadRequest(format!("invalid webhook url: {url}"));
+
+    let parsed = Url::parse(url).map_err(|_| invalid())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid());
+    }
+    let host = match parsed.host().ok_or_else(invalid)? {
+        url::Host::Domain(domain) => domain.to_string(),
+        url::Host::Ipv4(ip) => ip.to_string(),
+        url::Host::Ipv6(ip) => ip.to_string(),
+    };
+    let port = parsed.port_or_known_default().ok_or_else(invalid)?;
+
+    let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|_| Error::BadRequest(format!("webhook host {host} could not be resolved")))?
+        .collect();
+
+    if addrs.is_empty()
+        || addrs
+            .iter()
+            .any(|addr| !address_allowed(addr.ip(), allow_localhost))
+    {
+        return Err(Error::BadRequest(format!(
+            "webhook url {url} resolves to an internal address"
+        )));
+    }
+
+    Ok(())
+}
+
+/// resolves like the system resolver but drops internal addresses, so a host that changes its
+/// records after the webhook was created still can't be used to reach them
+struct PublicResolver {
+    settings: Arc<RwLock<InstanceSettings>>,
+}
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let settings = self.settings.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let allow_localhost = settings.read().await.webhook_allow_localhost;
+            let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| address_allowed(addr.ip(), allow_localhost))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// the secret is generated here and returned only this once
+pub async fn create_webhook(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    url: &str,
+) -> Result<CreatedWebhook, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Webhook, Action::Create).in_group(group_id))
+        .await?;
+
+    let allow_localhost = state.settings.read().await.webhook_allow_localhost;
+    validate_url(url, allow_localhost).await?;
+
+    let secrets_key = state
+        .config
+        .secrets_key
+        .as_ref()
+        .ok_or(SecretError::NotConfigured)?;
+
+    let raw: [u8; 32] = rand::random();
+    let secret = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw);
+
+    let id = Uuid::now_v7();
+    let encrypted = secrets_key.encrypt(group_id, &secret_name(id), &secret)?;
+    let webhook =
+        database::webhooks::create_webhook(&state.db, id, group_id, url, &encrypted).await?;
+
+    tracing::info!(group_id = %group_id, webhook_id = %webhook.id, "webhook created");
+
+    Ok(CreatedWebhook { webhook, secret })
+}
+
+pub async fn list_webhooks(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+) -> Result<Vec<GroupWebhook>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Webhook, Action::List).in_group(group_id))
+        .await?;
+
+    Ok(database::webhooks::list_webhooks(&state.db, group_id).await?)
+}
+
+pub async fn delete_webhook(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<(), Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(PermissionCheck::new(ResourceType::Webhook, Action::Delete).in_group(group_id))
+        .await?;
+
+    if !database::webhooks::delete_webhook(&state.db, group_id, webhook_id).await? {
+        return Err(Error::NotFound(format!("webhook {webhook_id} not found")));
+    }
+
+    tracing::info!(group_id = %group_id, webhook_id = %webhook_id, "webhook deleted");
+
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    state: &AppState,
+    entity: &AuthenticatedEntity,
+    group_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<Vec<WebhookDelivery>, Error> {
+    PermissionChecker::new(&state.db, entity)
+        .require(
+            PermissionCheck::new(ResourceType::Webhook, Action::Get)
+                .in_group(group_id)
+                .with_resource_id(webhook_id),
+        )
+        .await?;
+
+    if !database::webhooks::webhook_exists(&state.db, group_id, webhook_id).await? {
+        return Err(Error::NotFound(format!("webhook {webhook_id} not found")));
+    }
+
+    Ok(database::webhooks::list_deliveries(&state.db, webhook_id, 100).await?)
+}
+
+/// queues a delivery to every webhook of the group for a finished assembly. the payload is
+/// rendered now, so retries send the same body even after the assembly is cleaned up.
+pub async fn assembly_finished(
+    pool: &SqlitePool,
+    group_id: Uuid,
+    assembly_id: Uuid,
+) -> Result<(), Error> {
+    let Some(assembly) = database::assembly::get_assembly(pool, group_id, assembly_id).await?
+    else {
+        return Ok(());
+    };
+
+    let event = match assembly.status.as_str() {
+        "completed" => "assembly.completed",
+        "failed" => "assembly.failed",
+        _ => return Ok(()),
+    };
+
+    let payload = serde_json::to_string(&AssemblyEvent {
+        event,
+        assembly: &assembly,
+    })
+    .map_err(|e| Error::Internal(e.to_string()))?;
+
+    database::webhooks::enqueue_deliveries(pool, group_id, assembly_id, event, &payload).await?;
+
+    Ok(())
+}
+
+/// hex hmac-sha256 of the exact request body, sent as `sha256=<hex>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// 30s after the first failure, doubling up to an hour
+fn backoff(attempts: u32) -> chrono::Duration {
+    let secs = 30i64 << attempts.saturating_sub(1).min(7);
+    chrono::Duration::seconds(secs.min(3600))
+}
+
+/// the delivery queue lives in `webhook_deliveries`, so deliveries queued by standalone
+/// workers are sent too and nothing is lost across restarts
+pub async fn run(
+    pool: SqlitePool,
+    settings: Arc<RwLock<InstanceSettings>>,
+    secrets_key: Option<SecretsKey>,
+) {
+    // redirects aren't followed, they could point anywhere
+    let client = match reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver {
+            settings: settings.clone(),
+        }))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to build webhook client, webhooks are disabled");
+            return;
+        }
+    };
+
+    loop {
+        let claim_for = chrono::Duration::from_std(REQUEST_TIMEOUT * 3).unwrap_or_default();
+        match database::webhooks::claim_due_deliveries(&pool, 10, claim_for).await {
+            Ok(deliveries) => {
+                let allow_localhost = settings.read().await.webhook_allow_localhost;
+                for delivery in deliveries {
+                    deliver(
+                        &pool,
+                        &client,
+                        secrets_key.as_ref(),
+                        delivery,
+                        allow_localhost,
+                    )
+                    .await;
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "failed to claim webhook deliveries"),
+        }
+
+        if let Err(e) = database::webhooks::cleanup_old_deliveries(
+            &pool,
+            chrono::Duration::days(DELIVERY_LOG_DAYS),
+        )
+        .await
+        {
+            tracing::error!(error = %e, "webhook delivery cleanup failed");
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn deliver(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    secrets_key: Option<&SecretsKey>,
+    delivery: PendingDelivery,
+    allow_localhost: bool,
+) {
+    let signature = secrets_key
+        .ok_or(SecretError::NotConfigured)
+        .and_then(|key| {
+            key.decrypt(
+                delivery.group_id,
+                &secret_name(delivery.webhook_id),
+                &delivery.secret,
+            )
+        })
+        .map(|secret| sign(&secret, delivery.payload.as_bytes()));
+
+    // the resolver covers host names, ip literals in the url never reach it
+    let result = match (
+        signature,
+        validate_url(&delivery.url, allow_localhost).await,
+    ) {
+        (Ok(signature), Ok(())) => client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string()),
+        (Err(e), _) => Err(e.to_string()),
+        (_, Err(e)) => Err(e.to_string()),
+    };
+
+    let (response_status, error) = match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("receiver answered {}", response.status())),
+        ),
+        Err(e) => (None, Some(e)),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let (status, next_attempt_at) = match &error {
+        None => ("delivered", None),
+        Some(_) if attempts >= MAX_ATTEMPTS => ("failed", None),
+        Some(_) => ("pending", Some(Utc::now() + backoff(attempts))),
+    };
+
+    match &error {
+        None => {
+            tracing::info!(delivery_id = %delivery.id, event = %delivery.event, "webhook delivered")
+        }
+        Some(e) => tracing::warn!(
+            delivery_id = %delivery.id,
+            attempts,
+            error = %e,
+            "webhook delivery failed"
+        ),
+    }
+
+    if let Err(e) = database::webhooks::record_delivery_attempt(
+        pool,
+        delivery.id,
+        status,
+        next_attempt_at,
+        response_status,
+        error.as_deref(),
+    )
+    .await
+    {
+        tracing::error!(delivery_id = %delivery.id, error = %e, "failed to record webhook delivery");
+    }
+}